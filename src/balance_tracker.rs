@@ -0,0 +1,165 @@
+use std::{
+  sync::Arc,
+  collections::HashMap,
+};
+use solana_program_test::{tokio::sync::{Mutex}};
+use anchor_spl::token::TokenAccount;
+use anchor_lang::AccountDeserialize;
+use spl_associated_token_account::get_associated_token_address;
+use solana_sdk::{
+  pubkey::Pubkey,
+  signature::Signer,
+};
+use crate::{
+  program_test::ProgramTest,
+};
+
+#[derive(Default, Clone)]
+pub struct BalanceSnapshot {
+  pub lamports: HashMap<Pubkey, u64>,
+  /// Token balances keyed by (wallet, mint)
+  pub tokens: HashMap<(Pubkey, Pubkey), u64>,
+  pub fees_paid: u64,
+}
+
+pub struct BalanceDiff {
+  pub lamports: HashMap<Pubkey, i128>,
+  pub tokens: HashMap<(Pubkey, Pubkey), i128>,
+  pub fee_payer: Pubkey,
+  /// Fees paid by the fee payer between the two snapshots
+  pub fees: u64,
+}
+
+impl BalanceDiff {
+  pub fn lamport_delta(&self, wallet: &Pubkey) -> i128 {
+    *self.lamports
+      .get(wallet)
+      .unwrap_or_else(|| panic!("BALANCE-TRACKER-ERROR: Lamports of {} are not tracked", wallet))
+  }
+
+  pub fn token_delta(&self, wallet: &Pubkey, mint: &Pubkey) -> i128 {
+    *self.tokens
+      .get(&(*wallet, *mint))
+      .unwrap_or_else(|| panic!("BALANCE-TRACKER-ERROR: Tokens {} of {} are not tracked", mint, wallet))
+  }
+
+  /// Asserts the lamports change of the given wallet. Transaction fees are excluded from the delta
+  /// if the wallet is the fee payer.
+  pub fn assert_lamport_delta(&self, wallet: &Pubkey, expected: i128) {
+    let mut delta = self.lamport_delta(wallet);
+
+    if *wallet == self.fee_payer {
+      delta += self.fees as i128;
+    }
+
+    assert_eq!(delta, expected, "lamport delta mismatch for {}", wallet);
+  }
+
+  pub fn assert_token_delta(&self, wallet: &Pubkey, mint: &Pubkey, expected: i128) {
+    assert_eq!(
+      self.token_delta(wallet, mint),
+      expected,
+      "token delta mismatch for {} (mint {})",
+      wallet,
+      mint,
+    );
+  }
+}
+
+/// Keeps track of the lamport and token balances of a set of wallets so the effect of a transaction
+/// can be asserted without manually reading balances before and after.
+pub struct BalanceTracker {
+  pub program_test: Arc<Mutex<ProgramTest>>,
+  wallets: Vec<Pubkey>,
+  tokens: Vec<(Pubkey, Pubkey)>,
+  snapshot: BalanceSnapshot,
+}
+
+impl BalanceTracker {
+  pub fn new(program_test: Arc<Mutex<ProgramTest>>) -> Self {
+    Self {
+      program_test,
+      wallets: vec![],
+      tokens: vec![],
+      snapshot: BalanceSnapshot::default(),
+    }
+  }
+
+  pub fn track_lamports(&mut self, wallet: &Pubkey) -> &mut Self {
+    self.wallets.push(*wallet);
+    self
+  }
+
+  /// Tracks the balance of the wallet's associated token account for the given mint
+  pub fn track_tokens(&mut self, wallet: &Pubkey, mint: &Pubkey) -> &mut Self {
+    self.tokens.push((*wallet, *mint));
+    self
+  }
+
+  /// Reads the current balances of all tracked accounts. A missing account is considered to have a zero balance.
+  pub async fn take_snapshot(&mut self) -> BalanceSnapshot {
+    let mut lock_pt = self.program_test.lock().await;
+    let mut snapshot = BalanceSnapshot {
+      fees_paid: lock_pt.fees_paid,
+      ..Default::default()
+    };
+
+    for wallet in &self.wallets {
+      let lamports = lock_pt.context.banks_client.get_balance(*wallet).await.unwrap();
+      snapshot.lamports.insert(*wallet, lamports);
+    }
+
+    for (wallet, mint) in &self.tokens {
+      let ata = get_associated_token_address(wallet, mint);
+      let amount = lock_pt
+        .context
+        .banks_client
+        .get_account(ata)
+        .await
+        .unwrap()
+        .map(|account| TokenAccount::try_deserialize_unchecked(&mut account.data.as_ref()).unwrap().amount)
+        .unwrap_or(0);
+
+      snapshot.tokens.insert((*wallet, *mint), amount);
+    }
+
+    snapshot
+  }
+
+  pub async fn snapshot(&mut self) {
+    self.snapshot = self.take_snapshot().await;
+  }
+
+  /// Returns the changes since the last snapshot and makes the current balances the new baseline
+  pub async fn diff(&mut self) -> BalanceDiff {
+    let current = self.take_snapshot().await;
+    let fee_payer = self.program_test.lock().await.payer.pubkey();
+
+    let lamports = current.lamports
+      .iter()
+      .map(|(wallet, amount)| {
+        let before = self.snapshot.lamports.get(wallet).copied().unwrap_or(0);
+        (*wallet, *amount as i128 - before as i128)
+      })
+      .collect();
+
+    let tokens = current.tokens
+      .iter()
+      .map(|(key, amount)| {
+        let before = self.snapshot.tokens.get(key).copied().unwrap_or(0);
+        (*key, *amount as i128 - before as i128)
+      })
+      .collect();
+
+    let diff = BalanceDiff {
+      lamports,
+      tokens,
+      fee_payer,
+      fees: current.fees_paid - self.snapshot.fees_paid,
+    };
+
+    self.snapshot = current;
+
+    diff
+  }
+}
@@ -0,0 +1,105 @@
+use std::convert::TryInto;
+use solana_sdk::{
+  hash::hashv,
+  pubkey::Pubkey,
+  signature::{Keypair, Signer, keypair_from_seed},
+};
+
+/// Upper bound of keypairs generated while looking for one that satisfies an ordering constraint
+const MAX_ATTEMPTS: usize = 100_000;
+
+/// Generates keypairs from a seed so that the same test produces the same addresses on every run.
+/// The n-th keypair only depends on the seed and n.
+#[derive(Clone, Debug)]
+pub struct KeypairFactory {
+  pub seed: u64,
+  next_index: u64,
+}
+
+impl KeypairFactory {
+  pub fn from_seed(seed: u64) -> Self {
+    Self {
+      seed,
+      next_index: 0,
+    }
+  }
+
+  /// A factory with a random seed i.e. the equivalent of calling Keypair::new. The seed is public
+  /// so that it can be logged and a failing run replayed with from_seed.
+  pub fn random() -> Self {
+    let random_bytes = Keypair::new().pubkey().to_bytes();
+    Self::from_seed(u64::from_le_bytes(random_bytes[..8].try_into().unwrap()))
+  }
+
+  /// Returns the keypair at the given position without advancing the factory
+  pub fn keypair_at(&self, index: u64) -> Keypair {
+    let seed = hashv(&[b"solana-test-utils", &self.seed.to_le_bytes(), &index.to_le_bytes()]);
+    keypair_from_seed(seed.as_ref()).unwrap()
+  }
+
+  pub fn next_keypair(&mut self) -> Keypair {
+    let keypair = self.keypair_at(self.next_index);
+    self.next_index += 1;
+
+    keypair
+  }
+
+  pub fn next_keypairs(&mut self, count: usize) -> Vec<Keypair> {
+    (0..count).map(|_| self.next_keypair()).collect()
+  }
+
+  /// Keypairs whose pubkeys are in ascending order
+  pub fn ascending(&mut self, count: usize) -> Vec<Keypair> {
+    let mut keypairs = self.next_keypairs(count);
+    keypairs.sort_by_key(|keypair| keypair.pubkey());
+
+    keypairs
+  }
+
+  /// Keypairs whose pubkeys are in descending order
+  pub fn descending(&mut self, count: usize) -> Vec<Keypair> {
+    let mut keypairs = self.ascending(count);
+    keypairs.reverse();
+
+    keypairs
+  }
+
+  /// Keypairs ordered so that `keypairs[i]` has the `order[i]`-th smallest pubkey
+  /// e.g. `in_order(&[2, 0, 1])` returns the largest pubkey first
+  pub fn in_order(&mut self, order: &[usize]) -> Vec<Keypair> {
+    let mut sorted = self.ascending(order.len()).into_iter().map(Some).collect::<Vec<_>>();
+
+    order
+      .iter()
+      .map(|rank| {
+        sorted
+          .get_mut(*rank)
+          .and_then(Option::take)
+          .unwrap_or_else(|| panic!("KEYPAIR-ORDER-ERROR: {:?} is not a permutation", order))
+      })
+      .collect()
+  }
+
+  /// Next keypair whose pubkey is smaller than the given one
+  pub fn less_than(&mut self, pubkey: &Pubkey) -> Keypair {
+    self.find(|candidate| candidate < pubkey)
+  }
+
+  /// Next keypair whose pubkey is greater than the given one
+  pub fn greater_than(&mut self, pubkey: &Pubkey) -> Keypair {
+    self.find(|candidate| candidate > pubkey)
+  }
+
+  fn find(&mut self, predicate: impl Fn(&Pubkey) -> bool) -> Keypair {
+    (0..MAX_ATTEMPTS)
+      .map(|_| self.next_keypair())
+      .find(|keypair| predicate(&keypair.pubkey()))
+      .unwrap_or_else(|| panic!("KEYPAIR-ORDER-ERROR: no matching keypair after {} attempts", MAX_ATTEMPTS))
+  }
+}
+
+impl Default for KeypairFactory {
+  fn default() -> Self {
+    Self::random()
+  }
+}
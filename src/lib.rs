@@ -9,3 +9,6 @@ pub mod utils;
 pub mod serialization;
 pub use spl_token;
 pub mod metaplex;
+pub mod balance_tracker;
+pub mod keypair;
+pub mod logs;
@@ -0,0 +1,116 @@
+use std::str::FromStr;
+use regex::Regex;
+use solana_sdk::{
+  pubkey::Pubkey,
+  signature::Signature,
+  transaction::TransactionError,
+};
+
+/// When the logs of a transaction are printed to stdout
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogPrinting {
+  #[default]
+  Never,
+  OnFailure,
+  Always,
+}
+
+/// A line logged by a program e.g. with `msg!`, along with the program that was executing at the time
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramLog {
+  pub program_id: Pubkey,
+  /// 1 for programs invoked by the transaction, 2 for programs they invoke and so on
+  pub depth: usize,
+  pub message: String,
+}
+
+/// The logs of a single transaction as reported by the runtime
+#[derive(Clone, Debug)]
+pub struct TransactionLogs {
+  pub signature: Signature,
+  pub logs: Vec<String>,
+  pub result: Result<(), TransactionError>,
+  pub units_consumed: u64,
+}
+
+impl TransactionLogs {
+  /// The `Program log:` and `Program data:` lines attributed to the program that emitted them. The runtime's own
+  /// lines e.g. `Program ... invoke [1]` or `Program ... consumed ...` are skipped.
+  pub fn program_logs(&self) -> Vec<ProgramLog> {
+    let mut invoke_stack: Vec<Pubkey> = vec![];
+    let mut program_logs = vec![];
+
+    for line in &self.logs {
+      let words = line.split_whitespace().collect::<Vec<_>>();
+
+      match words.as_slice() {
+        ["Program", program_id, "invoke", depth] => {
+          let program_id = Pubkey::from_str(program_id).ok();
+          let depth = depth.trim_matches(|c| c == '[' || c == ']').parse::<usize>().ok();
+
+          // Programs loaded as native code with `processor!` log their invocations more than once, so only
+          // the line that opens the next level of the stack is taken into account
+          if let (Some(program_id), Some(depth)) = (program_id, depth) {
+            if depth == invoke_stack.len() + 1 {
+              invoke_stack.push(program_id);
+            }
+          }
+        }
+        ["Program", program_id, "success"] | ["Program", program_id, "failed:", ..] => {
+          if invoke_stack.last().map(|last| last.to_string()).as_deref() == Some(*program_id) {
+            invoke_stack.pop();
+          }
+        }
+        _ => {
+          let message = line
+            .strip_prefix("Program log: ")
+            .or_else(|| line.strip_prefix("Program data: "));
+
+          if let (Some(message), Some(program_id)) = (message, invoke_stack.last()) {
+            program_logs.push(ProgramLog {
+              program_id: *program_id,
+              depth: invoke_stack.len(),
+              message: message.to_string(),
+            });
+          }
+        }
+      }
+    }
+
+    program_logs
+  }
+
+  /// Messages logged by the given program itself, excluding the programs it invoked
+  pub fn logs_of(&self, program_id: &Pubkey) -> Vec<String> {
+    self.program_logs()
+      .into_iter()
+      .filter(|log| log.program_id == *program_id)
+      .map(|log| log.message)
+      .collect()
+  }
+
+  /// Messages logged at the given invoke depth, e.g. 1 for the top level instructions
+  pub fn logs_at_depth(&self, depth: usize) -> Vec<String> {
+    self.program_logs()
+      .into_iter()
+      .filter(|log| log.depth == depth)
+      .map(|log| log.message)
+      .collect()
+  }
+
+  pub fn contains(&self, needle: &str) -> bool {
+    self.logs.iter().any(|line| line.contains(needle))
+  }
+
+  pub fn matches(&self, pattern: &Regex) -> bool {
+    self.logs.iter().any(|line| pattern.is_match(line))
+  }
+
+  pub fn print(&self) {
+    println!("Logs of transaction {} ({:?}):", self.signature, self.result);
+
+    for line in &self.logs {
+      println!("  {}", line);
+    }
+  }
+}
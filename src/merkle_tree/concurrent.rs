@@ -0,0 +1,353 @@
+use std::convert::TryInto;
+use solana_sdk::{
+  keccak::hashv,
+  pubkey::Pubkey,
+};
+use crate::program_test::ProgramTest;
+
+pub type Node = [u8; 32];
+
+pub const EMPTY: Node = [0_u8; 32];
+
+/// Maximum depth supported by SPL account compression
+pub const MAX_SUPPORTED_DEPTH: u32 = 30;
+
+/// Size of the ConcurrentMerkleTreeHeader (V1) that precedes the tree in the account data
+pub const HEADER_SIZE: usize = 2 + 54;
+
+/// Hash of an empty subtree of the given level, level 0 being the leaves
+pub fn empty_node(level: u32) -> Node {
+  (0..level).fold(EMPTY, |node, _| hashv(&[&node, &node]).0)
+}
+
+/// Size of the ConcurrentMerkleTree struct i.e. sequence number, active index, buffer size,
+/// the changelog buffer and the rightmost proof
+pub fn merkle_tree_size(max_depth: u32, max_buffer_size: u32) -> usize {
+  let max_depth = max_depth as usize;
+  let change_log_size = 32 + 32 * max_depth + 8;
+  let path_size = 32 * max_depth + 32 + 8;
+
+  3 * 8 + max_buffer_size as usize * change_log_size + path_size
+}
+
+pub fn canopy_size(canopy_depth: u32) -> usize {
+  ((1 << (canopy_depth + 1)) - 2) * 32
+}
+
+/// Total size of an account compression tree account
+pub fn account_size(max_depth: u32, max_buffer_size: u32, canopy_depth: u32) -> usize {
+  HEADER_SIZE + merkle_tree_size(max_depth, max_buffer_size) + canopy_size(canopy_depth)
+}
+
+/// The fields of an on-chain ConcurrentMerkleTree account that are relevant when comparing it to a mirror
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConcurrentMerkleTreeAccount {
+  pub max_buffer_size: u32,
+  pub max_depth: u32,
+  pub authority: Pubkey,
+  pub creation_slot: u64,
+  pub sequence_number: u64,
+  pub root: Node,
+  /// Index of the next leaf to be appended
+  pub rightmost_index: u32,
+  pub rightmost_leaf: Node,
+  pub canopy: Vec<Node>,
+}
+
+impl ConcurrentMerkleTreeAccount {
+  /// Reads the account data as laid out by spl-account-compression. Returns None if the data is not
+  /// an initialized V1 tree or is inconsistent with the sizes stored in its header.
+  pub fn unpack(data: &[u8]) -> Option<Self> {
+    let u32_at = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+    let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+    let node_at = |offset: usize| -> Node { data[offset..offset + 32].try_into().unwrap() };
+
+    // account type ConcurrentMerkleTree and header version V1
+    if data.len() < HEADER_SIZE || data[0] != 1 || data[1] != 0 {
+      return None;
+    }
+
+    let max_buffer_size = u32_at(2);
+    let max_depth = u32_at(6);
+    let tree_size = merkle_tree_size(max_depth, max_buffer_size);
+
+    if max_depth > MAX_SUPPORTED_DEPTH || data.len() < HEADER_SIZE + tree_size {
+      return None;
+    }
+
+    let tree = HEADER_SIZE;
+    let change_log_size = 32 + 32 * max_depth as usize + 8;
+    let active_index = u64_at(tree + 8);
+
+    // the root is read from the changelog entry at active_index, which corrupted data could point past
+    if active_index >= max_buffer_size as u64 {
+      return None;
+    }
+
+    let active_index = active_index as usize;
+    let rightmost_proof = tree + 24 + max_buffer_size as usize * change_log_size;
+    let rightmost_leaf = rightmost_proof + 32 * max_depth as usize;
+
+    let canopy = data[HEADER_SIZE + tree_size..]
+      .chunks_exact(32)
+      .map(|chunk| chunk.try_into().unwrap())
+      .collect();
+
+    Some(Self {
+      max_buffer_size,
+      max_depth,
+      authority: Pubkey::new_from_array(data[10..42].try_into().unwrap()),
+      creation_slot: u64_at(42),
+      sequence_number: u64_at(tree),
+      root: node_at(tree + 24 + active_index * change_log_size),
+      rightmost_index: u32_at(rightmost_leaf + 32),
+      rightmost_leaf: node_at(rightmost_leaf),
+      canopy,
+    })
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MirrorMismatch {
+  AccountNotFound,
+  InvalidAccountData,
+  MaxDepth { expected: u32, found: u32 },
+  MaxBufferSize { expected: u32, found: u32 },
+  CanopyDepth { expected: u32, found_nodes: usize },
+  Root { expected: Node, found: Node },
+  SequenceNumber { expected: u64, found: u64 },
+  RightmostIndex { expected: u32, found: u32 },
+  RightmostLeaf { expected: Node, found: Node },
+  /// Index of the first canopy node that differs
+  Canopy(usize),
+}
+
+/// An off-chain replica of SPL account compression's ConcurrentMerkleTree. It is a fixed depth tree with
+/// positional keccak hashing, where missing leaves are EMPTY and missing subtrees are `empty_node(level)`.
+/// Apply the same appends and replacements as the program under test and compare with `validate`.
+///
+/// Note! The account layout is the one of the deployed program. ConcurrentMerkleTree is not `repr(C)` in
+/// spl-concurrent-merkle-tree 0.1, so accounts written by spl-account-compression loaded as native code
+/// with `processor!` may have the fields of the tree in a different order and fail validation.
+#[derive(Clone, Debug)]
+pub struct ConcurrentMerkleTreeMirror {
+  pub max_depth: u32,
+  pub max_buffer_size: u32,
+  pub canopy_depth: u32,
+  pub sequence_number: u64,
+  /// The non empty nodes of the tree, layer by layer starting from the leaves. Kept up to date on every change
+  /// so that only the path of the changed leaf is rehashed.
+  layers: Vec<Vec<Node>>,
+  /// `empty_node(level)` for every level of the tree
+  empty_nodes: Vec<Node>,
+}
+
+impl ConcurrentMerkleTreeMirror {
+  pub fn new(max_depth: u32, max_buffer_size: u32, canopy_depth: u32) -> Self {
+    if max_depth == 0 || max_depth > MAX_SUPPORTED_DEPTH {
+      panic!("CONCURRENT-TREE-ERROR: max depth {} is not supported", max_depth);
+    }
+
+    if !max_buffer_size.is_power_of_two() {
+      panic!("CONCURRENT-TREE-ERROR: max buffer size {} must be a power of 2", max_buffer_size);
+    }
+
+    if canopy_depth > max_depth {
+      panic!("CONCURRENT-TREE-ERROR: canopy depth {} exceeds max depth {}", canopy_depth, max_depth);
+    }
+
+    Self {
+      max_depth,
+      max_buffer_size,
+      canopy_depth,
+      sequence_number: 0,
+      layers: vec![vec![]; max_depth as usize + 1],
+      empty_nodes: (0..=max_depth).map(empty_node).collect(),
+    }
+  }
+
+  pub fn capacity(&self) -> u64 {
+    1 << self.max_depth
+  }
+
+  /// Number of leaves that have been appended i.e. the index of the next leaf
+  pub fn rightmost_index(&self) -> u32 {
+    self.layers[0].len() as u32
+  }
+
+  pub fn leaf(&self, index: u32) -> Node {
+    self.layers[0].get(index as usize).copied().unwrap_or(EMPTY)
+  }
+
+  pub fn append(&mut self, leaf: Node) -> &mut Self {
+    if self.layers[0].len() as u64 >= self.capacity() {
+      panic!("CONCURRENT-TREE-ERROR: tree is full");
+    }
+
+    self.set_leaf(self.rightmost_index(), leaf);
+
+    self
+  }
+
+  /// Same as the on-chain replace_leaf. Replacing the leaf at rightmost_index is equivalent to an append.
+  pub fn replace_leaf(&mut self, index: u32, leaf: Node) -> &mut Self {
+    if index > self.rightmost_index() {
+      panic!("CONCURRENT-TREE-ERROR: leaf index {} is out of bounds", index);
+    }
+
+    if index == self.rightmost_index() {
+      return self.append(leaf);
+    }
+
+    self.set_leaf(index, leaf);
+
+    self
+  }
+
+  /// Sets the leaf at an index that is either in use or rightmost_index and rehashes its path up to the root
+  fn set_leaf(&mut self, index: u32, leaf: Node) {
+    let mut index = index as usize;
+    let mut node = leaf;
+
+    for level in 0..=self.max_depth as usize {
+      let layer = &mut self.layers[level];
+
+      if index == layer.len() {
+        layer.push(node);
+      } else {
+        layer[index] = node;
+      }
+
+      if level == self.max_depth as usize {
+        break;
+      }
+
+      let sibling = self.node(level as u32, (index ^ 1) as u64);
+      node = if index & 1 == 0 {
+        hashv(&[&node, &sibling]).0
+      } else {
+        hashv(&[&sibling, &node]).0
+      };
+      index /= 2;
+    }
+
+    self.sequence_number += 1;
+  }
+
+  fn node(&self, level: u32, index: u64) -> Node {
+    self.layers[level as usize]
+      .get(index as usize)
+      .copied()
+      .unwrap_or(self.empty_nodes[level as usize])
+  }
+
+  pub fn root(&self) -> Node {
+    self.node(self.max_depth, 0)
+  }
+
+  /// Full proof of max_depth nodes for any leaf, including empty ones
+  pub fn proof(&self, index: u32) -> Vec<Node> {
+    if index as u64 >= self.capacity() {
+      panic!("CONCURRENT-TREE-ERROR: leaf index {} is out of bounds", index);
+    }
+
+    (0..self.max_depth)
+      .map(|level| self.node(level, (index as u64 >> level) ^ 1))
+      .collect()
+  }
+
+  /// The proof nodes that must be passed to the program, the rest are read from the canopy on-chain
+  pub fn canopy_proof(&self, index: u32) -> Vec<Node> {
+    let mut proof = self.proof(index);
+    proof.truncate((self.max_depth - self.canopy_depth) as usize);
+
+    proof
+  }
+
+  /// The canopy in the on-chain layout i.e. the top of the tree without the root, in breadth first order
+  pub fn canopy(&self) -> Vec<Node> {
+    (2..(1_u64 << (self.canopy_depth + 1)))
+      .map(|node_index| {
+        let height = 63 - node_index.leading_zeros();
+        self.node(self.max_depth - height, node_index - (1 << height))
+      })
+      .collect()
+  }
+
+  pub fn verify(&self, proof: &[Node], index: u32, leaf: Node) -> bool {
+    let computed = proof.iter().enumerate().fold(leaf, |node, (level, sibling)| {
+      if (index >> level) & 1 == 0 {
+        hashv(&[&node, sibling]).0
+      } else {
+        hashv(&[sibling, &node]).0
+      }
+    });
+
+    computed == self.root()
+  }
+
+  /// Compares the mirror with the data of an on-chain tree account
+  pub fn validate(&self, data: &[u8]) -> Result<(), MirrorMismatch> {
+    let account = ConcurrentMerkleTreeAccount::unpack(data).ok_or(MirrorMismatch::InvalidAccountData)?;
+
+    if account.max_depth != self.max_depth {
+      return Err(MirrorMismatch::MaxDepth {expected: self.max_depth, found: account.max_depth});
+    }
+
+    if account.max_buffer_size != self.max_buffer_size {
+      return Err(MirrorMismatch::MaxBufferSize {expected: self.max_buffer_size, found: account.max_buffer_size});
+    }
+
+    let canopy = self.canopy();
+    if account.canopy.len() != canopy.len() {
+      return Err(MirrorMismatch::CanopyDepth {expected: self.canopy_depth, found_nodes: account.canopy.len()});
+    }
+
+    let root = self.root();
+    if account.root != root {
+      return Err(MirrorMismatch::Root {expected: root, found: account.root});
+    }
+
+    if account.sequence_number != self.sequence_number {
+      return Err(MirrorMismatch::SequenceNumber {expected: self.sequence_number, found: account.sequence_number});
+    }
+
+    if account.rightmost_index != self.rightmost_index() {
+      return Err(MirrorMismatch::RightmostIndex {expected: self.rightmost_index(), found: account.rightmost_index});
+    }
+
+    let rightmost_leaf = self.rightmost_index().checked_sub(1).map(|index| self.leaf(index)).unwrap_or(EMPTY);
+    if account.rightmost_leaf != rightmost_leaf {
+      return Err(MirrorMismatch::RightmostLeaf {expected: rightmost_leaf, found: account.rightmost_leaf});
+    }
+
+    // Nodes that were never touched on-chain are left as EMPTY in the canopy and inferred when needed
+    for (i, (expected, found)) in canopy.iter().zip(account.canopy.iter()).enumerate() {
+      let height = 63 - ((i + 2) as u64).leading_zeros();
+      let is_untouched = *found == EMPTY && *expected == self.empty_nodes[(self.max_depth - height) as usize];
+
+      if expected != found && !is_untouched {
+        return Err(MirrorMismatch::Canopy(i));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Reads the tree account from the bank and compares it with the mirror
+  pub async fn validate_account(
+    &self,
+    program_test: &mut ProgramTest,
+    tree_account: Pubkey,
+  ) -> Result<(), MirrorMismatch> {
+    let account = program_test
+      .context
+      .banks_client
+      .get_account(tree_account)
+      .await
+      .unwrap()
+      .ok_or(MirrorMismatch::AccountNotFound)?;
+
+    self.validate(&account.data)
+  }
+}
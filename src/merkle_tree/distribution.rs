@@ -0,0 +1,133 @@
+use solana_sdk::pubkey::Pubkey;
+use super::{
+  MerkleTree,
+  MultiProof,
+  MerkleHasher,
+  SolanaHasher,
+  MerkleError,
+  leaf::LeafFormat,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Claim {
+  pub index: u64,
+  pub claimant: Pubkey,
+  pub amount: u64,
+}
+
+/// A merkle airdrop distribution i.e. a list of claims along with the tree built from their leaves
+pub struct Distribution<H: MerkleHasher = SolanaHasher> {
+  pub claims: Vec<Claim>,
+  pub leaf_format: LeafFormat,
+  pub double_hashed: bool,
+  leaves: Vec<[u8; 32]>,
+  tree: MerkleTree<H>,
+}
+
+impl Distribution {
+  /// Each claim gets the index of its position in the given list
+  pub fn new(claims: &[(Pubkey, u64)], leaf_format: LeafFormat, double_hashed: bool) -> Self {
+    Self::with_hasher(claims, leaf_format, double_hashed)
+  }
+
+  /// Same as new but fails on an empty list of claims or claims that produce the same leaf e.g. the same
+  /// claimant and amount listed twice with LeafFormat::Claim
+  pub fn try_new(claims: &[(Pubkey, u64)], leaf_format: LeafFormat, double_hashed: bool) -> Result<Self, MerkleError> {
+    Self::try_with_hasher(claims, leaf_format, double_hashed)
+  }
+}
+
+impl<H: MerkleHasher> Distribution<H> {
+  /// Same as new for trees that use a different hasher
+  pub fn with_hasher(claims: &[(Pubkey, u64)], leaf_format: LeafFormat, double_hashed: bool) -> Self {
+    let (claims, leaves) = Self::claims_and_leaves(claims, leaf_format, double_hashed);
+
+    Self {
+      claims,
+      leaf_format,
+      double_hashed,
+      tree: MerkleTree::from_leaves(leaves.clone()),
+      leaves,
+    }
+  }
+
+  pub fn try_with_hasher(
+    claims: &[(Pubkey, u64)],
+    leaf_format: LeafFormat,
+    double_hashed: bool,
+  ) -> Result<Self, MerkleError> {
+    let (claims, leaves) = Self::claims_and_leaves(claims, leaf_format, double_hashed);
+
+    Ok(Self {
+      claims,
+      leaf_format,
+      double_hashed,
+      tree: MerkleTree::try_from_leaves(leaves.clone())?,
+      leaves,
+    })
+  }
+
+  fn claims_and_leaves(
+    claims: &[(Pubkey, u64)],
+    leaf_format: LeafFormat,
+    double_hashed: bool,
+  ) -> (Vec<Claim>, Vec<[u8; 32]>) {
+    let claims = claims
+      .iter()
+      .enumerate()
+      .map(|(index, (claimant, amount))| Claim {
+        index: index as u64,
+        claimant: *claimant,
+        amount: *amount,
+      })
+      .collect::<Vec<_>>();
+
+    let leaves = claims
+      .iter()
+      .map(|claim| leaf_format.leaf(claim.index, &claim.claimant, claim.amount, double_hashed))
+      .collect::<Vec<_>>();
+
+    (claims, leaves)
+  }
+
+  pub fn root(&self) -> Option<[u8; 32]> {
+    self.tree.root()
+  }
+
+  pub fn tree(&self) -> &MerkleTree<H> {
+    &self.tree
+  }
+
+  pub fn leaves(&self) -> &[[u8; 32]] {
+    &self.leaves
+  }
+
+  pub fn total_amount(&self) -> u128 {
+    self.claims.iter().map(|claim| claim.amount as u128).sum()
+  }
+
+  /// Returns the first claim of the given claimant
+  pub fn claim(&self, claimant: &Pubkey) -> Option<&Claim> {
+    self.claims.iter().find(|claim| claim.claimant == *claimant)
+  }
+
+  pub fn leaf(&self, claimant: &Pubkey) -> Option<[u8; 32]> {
+    let claim = self.claim(claimant)?;
+    Some(self.leaves[claim.index as usize])
+  }
+
+  pub fn proof(&self, claimant: &Pubkey) -> Option<Vec<[u8; 32]>> {
+    let claim = self.claim(claimant)?;
+    Some(self.tree.proof(&[claim.index as usize]))
+  }
+
+  /// Returns a single proof for all the claims of the given claimants
+  pub fn multi_proof(&self, claimants: &[Pubkey]) -> Option<MultiProof> {
+    let indices = claimants
+      .iter()
+      .map(|claimant| self.claim(claimant).map(|claim| claim.index as usize))
+      .collect::<Option<Vec<_>>>()?;
+
+    Some(self.tree.multi_proof(&indices))
+  }
+}
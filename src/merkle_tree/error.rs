@@ -0,0 +1,29 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MerkleError {
+  /// The tree has no leaves so there is neither a root nor any proof
+  EmptyTree,
+  IndexOutOfRange { index: usize, leaves: usize },
+  /// The leaf at `index` is identical to the one at `duplicate_of`
+  DuplicateLeaf { index: usize, duplicate_of: usize },
+  /// A proof was requested for an empty list of leaves
+  NoIndices,
+}
+
+impl fmt::Display for MerkleError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MerkleError::EmptyTree => write!(f, "the tree has no leaves"),
+      MerkleError::IndexOutOfRange { index, leaves } => {
+        write!(f, "index {} is out of range for {} leaves", index, leaves)
+      }
+      MerkleError::DuplicateLeaf { index, duplicate_of } => {
+        write!(f, "leaf {} is a duplicate of leaf {}", index, duplicate_of)
+      }
+      MerkleError::NoIndices => write!(f, "no leaf indices to prove"),
+    }
+  }
+}
+
+impl std::error::Error for MerkleError {}
@@ -0,0 +1,110 @@
+use std::marker::PhantomData;
+use solana_sdk::{keccak, hash};
+use rs_merkle::Hasher;
+
+pub trait HashFunction: Clone {
+  const NAME: &'static str;
+
+  fn hashv(data: &[&[u8]]) -> [u8; 32];
+}
+
+#[derive(Clone)]
+pub struct Keccak;
+
+impl HashFunction for Keccak {
+  const NAME: &'static str = "keccak";
+
+  fn hashv(data: &[&[u8]]) -> [u8; 32] {
+    keccak::hashv(data).0
+  }
+}
+
+#[derive(Clone)]
+pub struct Sha256;
+
+impl HashFunction for Sha256 {
+  const NAME: &'static str = "sha256";
+
+  fn hashv(data: &[&[u8]]) -> [u8; 32] {
+    hash::hashv(data).to_bytes()
+  }
+}
+
+/// Hashing strategy of a MerkleTree i.e. the hash function along with the way two sibling nodes are combined
+pub trait MerkleHasher: Hasher<Hash = [u8; 32]> {
+  /// Hashes two sibling nodes given in the order they appear in the tree
+  fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32];
+
+  /// Name stored in JSON exports e.g. `keccak_sorted` so a tree is never loaded with the wrong hasher
+  fn name() -> String;
+}
+
+/// Sorts the two nodes before hashing them, so proofs do not need to carry the position of each node.
+/// This is what OpenZeppelin's MerkleProof uses.
+#[derive(Clone)]
+pub struct SortedPairHasher<F: HashFunction>(PhantomData<F>);
+
+impl<F: HashFunction> Hasher for SortedPairHasher<F> {
+  type Hash = [u8; 32];
+
+  fn hash(data: &[u8]) -> Self::Hash {
+    F::hashv(&[data])
+  }
+
+  fn concat_and_hash(left: &Self::Hash, right: Option<&Self::Hash>) -> Self::Hash {
+    match right {
+      Some(right) => Self::hash_pair(left, right),
+      None => *left,
+    }
+  }
+}
+
+impl<F: HashFunction> MerkleHasher for SortedPairHasher<F> {
+  fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    if left <= right {
+      F::hashv(&[left, right])
+    } else {
+      F::hashv(&[right, left])
+    }
+  }
+
+  fn name() -> String {
+    format!("{}_sorted", F::NAME)
+  }
+}
+
+/// Hashes the two nodes in the order they appear in the tree. Proofs must carry the position of each node
+/// see `MerkleTree::positional_proof`.
+#[derive(Clone)]
+pub struct PositionalHasher<F: HashFunction>(PhantomData<F>);
+
+impl<F: HashFunction> Hasher for PositionalHasher<F> {
+  type Hash = [u8; 32];
+
+  fn hash(data: &[u8]) -> Self::Hash {
+    F::hashv(&[data])
+  }
+
+  fn concat_and_hash(left: &Self::Hash, right: Option<&Self::Hash>) -> Self::Hash {
+    match right {
+      Some(right) => Self::hash_pair(left, right),
+      None => *left,
+    }
+  }
+}
+
+impl<F: HashFunction> MerkleHasher for PositionalHasher<F> {
+  fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    F::hashv(&[left, right])
+  }
+
+  fn name() -> String {
+    format!("{}_positional", F::NAME)
+  }
+}
+
+/// keccak with sorted pairs which is the default hasher of MerkleTree
+pub type SolanaHasher = SortedPairHasher<Keccak>;
+pub type Sha256SortedHasher = SortedPairHasher<Sha256>;
+pub type KeccakPositionalHasher = PositionalHasher<Keccak>;
+pub type Sha256PositionalHasher = PositionalHasher<Sha256>;
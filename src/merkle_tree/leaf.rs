@@ -0,0 +1,43 @@
+use solana_sdk::{
+  keccak::hashv,
+  pubkey::Pubkey,
+};
+
+/// keccak(claimant || amount) with the amount encoded as little endian
+pub fn claim_leaf(claimant: &Pubkey, amount: u64) -> [u8; 32] {
+  hashv(&[claimant.as_ref(), &amount.to_le_bytes()]).0
+}
+
+/// keccak(index || claimant || amount) with the numbers encoded as little endian
+pub fn indexed_claim_leaf(index: u64, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+  hashv(&[&index.to_le_bytes(), claimant.as_ref(), &amount.to_le_bytes()]).0
+}
+
+/// Hashes an already hashed leaf once more. Since leaves are then never 64 bytes long, they can not be
+/// confused with an internal node which protects against second preimage attacks.
+pub fn double_hash(leaf: [u8; 32]) -> [u8; 32] {
+  hashv(&[&leaf]).0
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeafFormat {
+  /// See `claim_leaf`
+  Claim,
+  /// See `indexed_claim_leaf`
+  IndexedClaim,
+}
+
+impl LeafFormat {
+  pub fn leaf(&self, index: u64, claimant: &Pubkey, amount: u64, double_hashed: bool) -> [u8; 32] {
+    let leaf = match self {
+      LeafFormat::Claim => claim_leaf(claimant, amount),
+      LeafFormat::IndexedClaim => indexed_claim_leaf(index, claimant, amount),
+    };
+
+    if double_hashed {
+      double_hash(leaf)
+    } else {
+      leaf
+    }
+  }
+}
@@ -0,0 +1,382 @@
+pub mod leaf;
+pub mod distribution;
+pub mod hasher;
+pub mod serialization;
+pub mod concurrent;
+pub mod error;
+
+pub use distribution::{Distribution, Claim};
+pub use concurrent::ConcurrentMerkleTreeMirror;
+pub use error::MerkleError;
+pub use hasher::{
+  MerkleHasher, SolanaHasher, Sha256SortedHasher, KeccakPositionalHasher, Sha256PositionalHasher,
+};
+
+use std::collections::HashMap;
+use solana_sdk::keccak::hashv;
+use borsh::{BorshSerialize, BorshDeserialize};
+use rs_merkle::MerkleTree as MerkleTreeLib;
+
+/// A proof for multiple leaves in a format that can be passed to an on-chain program as instruction data
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MultiProof {
+  /// Indices of the proven leaves in ascending order
+  pub leaf_indices: Vec<u32>,
+  pub proof: Vec<[u8; 32]>,
+  pub total_leaves: u32,
+}
+
+impl MultiProof {
+  /// Verifies the proof given the leaves in the same order as `leaf_indices`
+  pub fn verify(&self, root: [u8; 32], leaves: &[[u8; 32]]) -> bool {
+    self.verify_with::<SolanaHasher>(root, leaves)
+  }
+
+  /// Same as verify for trees that use a different hasher
+  pub fn verify_with<H: MerkleHasher>(&self, root: [u8; 32], leaves: &[[u8; 32]]) -> bool {
+    if leaves.len() != self.leaf_indices.len() {
+      return false;
+    }
+
+    let leaves_with_indices = self.leaf_indices
+      .iter()
+      .zip(leaves.iter())
+      .map(|(index, leaf)| (*index as usize, *leaf))
+      .collect::<Vec<_>>();
+
+    verify_multi_proof::<H>(&self.proof, root, &leaves_with_indices, self.total_leaves as usize)
+  }
+}
+
+/// A proof node that carries the position of the sibling, needed by trees using a PositionalHasher
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofNode {
+  pub hash: [u8; 32],
+  /// True if this is the left sibling i.e. it goes first when hashed with the computed hash
+  pub is_left: bool,
+}
+
+/// State of the tree before a change, used for rollbacks and the root history
+struct Snapshot {
+  root: Option<[u8; 32]>,
+  leaves: Vec<[u8; 32]>,
+}
+
+pub struct MerkleTree<H: MerkleHasher = SolanaHasher> {
+  tree: MerkleTreeLib<H>,
+  history: Vec<Snapshot>,
+}
+
+impl MerkleTree {
+  pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+    Self::from_leaves(leaves)
+  }
+
+  pub fn try_new(leaves: Vec<[u8; 32]>) -> Result<Self, MerkleError> {
+    Self::try_from_leaves(leaves)
+  }
+
+  /// Note this is the exact same logic that will be used in the on-chain program as well
+  /// this is why we do not use the verify function from the underlying lib (rs_merkle)
+  pub fn verify(proof: Vec<[u8; 32]>, root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let mut computed_hash = leaf;
+
+    for proof_element in proof.into_iter() {
+      if computed_hash <= proof_element {
+        // Hash(current computed hash + current element of the proof)
+        computed_hash = hashv(&[&computed_hash, &proof_element]).0;
+      } else {
+        // Hash(current element of the proof + current computed hash)
+        computed_hash = hashv(&[&proof_element, &computed_hash]).0;
+      }
+    }
+    // Check if the computed hash (root) is equal to the provided root
+    computed_hash == root
+  }
+
+  /// Verifies a multi leaf proof as produced by `MerkleTree::proof`. Use `MultiProof::verify_with` for trees
+  /// that use a different hasher.
+  pub fn verify_multi(
+    proof: &[[u8; 32]],
+    root: [u8; 32],
+    leaves_with_indices: &[(usize, [u8; 32])],
+    total_leaves: usize,
+  ) -> bool {
+    verify_multi_proof::<SolanaHasher>(proof, root, leaves_with_indices, total_leaves)
+  }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+  /// Builds a tree using the hasher H e.g. `MerkleTree::<Sha256PositionalHasher>::from_leaves(leaves)`
+  pub fn from_leaves(leaves: Vec<[u8; 32]>) -> Self {
+    Self {
+      tree: MerkleTreeLib::<H>::from_leaves(&leaves),
+      history: vec![],
+    }
+  }
+
+  /// Same as from_leaves but rejects empty trees and duplicate leaves. A duplicate leaf would let the same
+  /// claim be proven twice under different indices. A single leaf is valid; the root is the leaf itself
+  /// and its proof is empty.
+  pub fn try_from_leaves(leaves: Vec<[u8; 32]>) -> Result<Self, MerkleError> {
+    if leaves.is_empty() {
+      return Err(MerkleError::EmptyTree);
+    }
+
+    if let Some((index, duplicate_of)) = Self::find_duplicate_leaf(&leaves) {
+      return Err(MerkleError::DuplicateLeaf {index, duplicate_of});
+    }
+
+    Ok(Self::from_leaves(leaves))
+  }
+
+  /// Returns the index of the first leaf that repeats an earlier one along with the index of the earlier one
+  pub fn find_duplicate_leaf(leaves: &[[u8; 32]]) -> Option<(usize, usize)> {
+    let mut seen = HashMap::with_capacity(leaves.len());
+
+    leaves
+      .iter()
+      .enumerate()
+      .find_map(|(index, leaf)| seen.insert(leaf, index).map(|duplicate_of| (index, duplicate_of)))
+  }
+
+  fn check_index(&self, index: usize) -> Result<(), MerkleError> {
+    let leaves = self.tree.leaves_len();
+
+    if leaves == 0 {
+      return Err(MerkleError::EmptyTree);
+    }
+
+    if index >= leaves {
+      return Err(MerkleError::IndexOutOfRange {index, leaves});
+    }
+
+    Ok(())
+  }
+
+  fn snapshot(&mut self) {
+    self.history.push(Snapshot {
+      root: self.root(),
+      leaves: self.leaves(),
+    });
+  }
+
+  /// Appends the given leaves creating a new root
+  pub fn append(&mut self, leaves: Vec<[u8; 32]>) -> &mut Self {
+    self.snapshot();
+
+    let mut leaves = leaves;
+    self.tree.append(&mut leaves).commit();
+
+    self
+  }
+
+  /// Replaces the leaf at the given index creating a new root. Since rs_merkle does not support updates
+  /// the tree is rebuilt from its leaves.
+  pub fn update_leaf(&mut self, index: usize, leaf: [u8; 32]) -> &mut Self {
+    self
+      .try_update_leaf(index, leaf)
+      .unwrap_or_else(|e| panic!("UPDATE-LEAF-ERROR: {}", e))
+  }
+
+  pub fn try_update_leaf(&mut self, index: usize, leaf: [u8; 32]) -> Result<&mut Self, MerkleError> {
+    self.check_index(index)?;
+
+    let mut leaves = self.leaves();
+    self.snapshot();
+    leaves[index] = leaf;
+    self.tree = MerkleTreeLib::<H>::from_leaves(&leaves);
+
+    Ok(self)
+  }
+
+  /// Reverts the latest append or update_leaf. Returns false if there is nothing to revert.
+  pub fn rollback(&mut self) -> bool {
+    match self.history.pop() {
+      Some(snapshot) => {
+        self.tree = MerkleTreeLib::<H>::from_leaves(&snapshot.leaves);
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Roots the tree had before each of the changes, oldest first
+  pub fn root_history(&self) -> Vec<[u8; 32]> {
+    self.history.iter().filter_map(|snapshot| snapshot.root).collect()
+  }
+
+  /// Returns true if the given root is either the current or any of the previous roots
+  pub fn is_known_root(&self, root: &[u8; 32]) -> bool {
+    self.root().as_ref() == Some(root) || self.root_history().contains(root)
+  }
+
+  pub fn root(&self) -> Option<[u8; 32]> {
+    self.tree.root()
+  }
+
+  pub fn root_hex(&self) -> Option<String> {
+    self.tree.root_hex()
+  }
+
+  pub fn leaves(&self) -> Vec<[u8; 32]> {
+    self.tree.leaves().unwrap_or_default()
+  }
+
+  /// Panics if the tree is empty or any of the indices is out of range. rs_merkle itself does not check
+  /// the indices and silently returns an invalid proof. An empty list of indices has an empty proof.
+  pub fn proof(&self, indices_to_prove: &[usize]) -> Vec<[u8; 32]> {
+    if indices_to_prove.is_empty() {
+      return vec![];
+    }
+
+    self
+      .try_proof(indices_to_prove)
+      .unwrap_or_else(|e| panic!("PROOF-ERROR: {}", e))
+  }
+
+  /// Same as proof but fails instead of panicking, and also fails on an empty list of indices
+  pub fn try_proof(&self, indices_to_prove: &[usize]) -> Result<Vec<[u8; 32]>, MerkleError> {
+    if indices_to_prove.is_empty() {
+      return Err(MerkleError::NoIndices);
+    }
+
+    for index in indices_to_prove {
+      self.check_index(*index)?;
+    }
+
+    Ok(self.tree.proof(indices_to_prove).proof_hashes().to_vec())
+  }
+
+  /// Returns a proof for all the given leaves. Indices are sorted and deduplicated first since this is what
+  /// the verification algorithm expects.
+  pub fn multi_proof(&self, indices_to_prove: &[usize]) -> MultiProof {
+    if indices_to_prove.is_empty() {
+      return MultiProof {
+        leaf_indices: vec![],
+        proof: vec![],
+        total_leaves: self.tree.leaves_len() as u32,
+      };
+    }
+
+    self
+      .try_multi_proof(indices_to_prove)
+      .unwrap_or_else(|e| panic!("PROOF-ERROR: {}", e))
+  }
+
+  pub fn try_multi_proof(&self, indices_to_prove: &[usize]) -> Result<MultiProof, MerkleError> {
+    let mut leaf_indices = indices_to_prove.to_vec();
+    leaf_indices.sort_unstable();
+    leaf_indices.dedup();
+
+    Ok(MultiProof {
+      proof: self.try_proof(&leaf_indices)?,
+      leaf_indices: leaf_indices.into_iter().map(|index| index as u32).collect(),
+      total_leaves: self.tree.leaves_len() as u32,
+    })
+  }
+
+  /// Returns the proof of a single leaf along with the position of each node
+  pub fn positional_proof(&self, index: usize) -> Vec<ProofNode> {
+    self
+      .try_positional_proof(index)
+      .unwrap_or_else(|e| panic!("PROOF-ERROR: {}", e))
+  }
+
+  pub fn try_positional_proof(&self, index: usize) -> Result<Vec<ProofNode>, MerkleError> {
+    let mut hashes = self.try_proof(&[index])?.into_iter();
+    let mut nodes = vec![];
+    let mut index = index;
+    let mut layer_len = self.tree.leaves_len();
+
+    while layer_len > 1 {
+      let is_right = index % 2 == 1;
+      // the last node of a layer with an odd number of nodes has no sibling
+      let is_promoted = !is_right && index + 1 == layer_len;
+
+      if !is_promoted {
+        if let Some(hash) = hashes.next() {
+          nodes.push(ProofNode {
+            hash,
+            is_left: is_right,
+          });
+        }
+      }
+
+      index /= 2;
+      layer_len = layer_len / 2 + layer_len % 2;
+    }
+
+    Ok(nodes)
+  }
+
+  pub fn verify_positional(proof: &[ProofNode], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed_hash = proof.iter().fold(leaf, |computed_hash, node| {
+      if node.is_left {
+        H::hash_pair(&node.hash, &computed_hash)
+      } else {
+        H::hash_pair(&computed_hash, &node.hash)
+      }
+    });
+
+    computed_hash == root
+  }
+}
+
+/// The rs_merkle multi proof algorithm written the way an on-chain program would implement it. The tree is rebuilt
+/// layer by layer starting from the known leaves; whenever the sibling of a known node is not known itself, the next
+/// hash is taken from the proof. The last node of a layer with an odd number of nodes is promoted to the next layer
+/// as is.
+fn verify_multi_proof<H: MerkleHasher>(
+  proof: &[[u8; 32]],
+  root: [u8; 32],
+  leaves_with_indices: &[(usize, [u8; 32])],
+  total_leaves: usize,
+) -> bool {
+  let mut layer = leaves_with_indices.to_vec();
+  layer.sort_by_key(|(index, _)| *index);
+  layer.dedup_by_key(|(index, _)| *index);
+
+  if layer.is_empty() || layer.iter().any(|(index, _)| *index >= total_leaves) {
+    return false;
+  }
+
+  let mut proof = proof.iter();
+  let mut layer_len = total_leaves;
+
+  while layer_len > 1 {
+    let mut parents = Vec::with_capacity(layer.len());
+    let mut i = 0;
+
+    while i < layer.len() {
+      let (index, hash) = layer[i];
+
+      let parent = if index % 2 == 1 {
+        // the left sibling is not known otherwise it would have been consumed in the previous iteration
+        match proof.next() {
+          Some(sibling) => H::hash_pair(sibling, &hash),
+          None => return false,
+        }
+      } else if index + 1 == layer_len {
+        hash
+      } else if i + 1 < layer.len() && layer[i + 1].0 == index + 1 {
+        i += 1;
+        H::hash_pair(&hash, &layer[i].1)
+      } else {
+        match proof.next() {
+          Some(sibling) => H::hash_pair(&hash, sibling),
+          None => return false,
+        }
+      };
+
+      parents.push((index / 2, parent));
+      i += 1;
+    }
+
+    layer = parents;
+    layer_len = layer_len / 2 + layer_len % 2;
+  }
+
+  // all proof hashes must have been used
+  proof.next().is_none() && layer[0].1 == root
+}
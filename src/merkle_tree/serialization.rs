@@ -0,0 +1,319 @@
+use std::{
+  fmt,
+  str::FromStr,
+};
+use serde::{Serialize, Deserialize};
+use solana_sdk::pubkey::Pubkey;
+use super::{
+  MerkleTree,
+  MerkleHasher,
+  Distribution,
+  leaf::LeafFormat,
+};
+
+#[derive(Debug)]
+pub enum SerializationError {
+  Json(serde_json::Error),
+  /// Invalid CSV row along with its 1-based line number
+  Csv(usize, String),
+  InvalidPubkey(String),
+  InvalidHash(String),
+  /// The root stored in the file does not match the root of the rebuilt tree
+  RootMismatch,
+  /// The file was exported with a different hasher than the one it is loaded with
+  HasherMismatch { expected: String, found: String },
+  /// Sorted claim indices must be 0, 1, 2, ... since each index is the position of the leaf in the tree
+  NonContiguousIndex { expected: u64, found: u64 },
+  /// The stored leaf of the claim with the given index does not match its claimant and amount
+  LeafMismatch(u64),
+  /// The stored proof of the claim with the given index does not match the rebuilt tree
+  ProofMismatch(u64),
+}
+
+impl fmt::Display for SerializationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SerializationError::Json(e) => write!(f, "invalid json: {}", e),
+      SerializationError::Csv(line, reason) => write!(f, "invalid csv at line {}: {}", line, reason),
+      SerializationError::InvalidPubkey(value) => write!(f, "invalid pubkey {}", value),
+      SerializationError::InvalidHash(value) => write!(f, "invalid hash {}", value),
+      SerializationError::RootMismatch => write!(f, "root does not match the leaves"),
+      SerializationError::HasherMismatch { expected, found } => {
+        write!(f, "expected hasher {} found {}", expected, found)
+      }
+      SerializationError::NonContiguousIndex { expected, found } => {
+        write!(f, "expected claim index {} found {}", expected, found)
+      }
+      SerializationError::LeafMismatch(index) => write!(f, "leaf of claim {} does not match the claim", index),
+      SerializationError::ProofMismatch(index) => write!(f, "proof of claim {} does not match the tree", index),
+    }
+  }
+}
+
+impl std::error::Error for SerializationError {}
+
+impl From<serde_json::Error> for SerializationError {
+  fn from(e: serde_json::Error) -> Self {
+    SerializationError::Json(e)
+  }
+}
+
+/// Lowercase hex without the 0x prefix which is the same format rs_merkle uses for root_hex
+pub fn to_hex(hash: &[u8; 32]) -> String {
+  hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Parses a 32 bytes hex string with or without the 0x prefix
+pub fn from_hex(value: &str) -> Result<[u8; 32], SerializationError> {
+  let hex = value.trim_start_matches("0x");
+
+  if hex.len() != 64 || !hex.is_ascii() {
+    return Err(SerializationError::InvalidHash(value.to_string()));
+  }
+
+  let mut hash = [0_u8; 32];
+  for (i, byte) in hash.iter_mut().enumerate() {
+    *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+      .map_err(|_| SerializationError::InvalidHash(value.to_string()))?;
+  }
+
+  Ok(hash)
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, SerializationError> {
+  Pubkey::from_str(value).map_err(|_| SerializationError::InvalidPubkey(value.to_string()))
+}
+
+fn check_hasher<H: MerkleHasher>(hasher: &str) -> Result<(), SerializationError> {
+  if hasher != H::name() {
+    return Err(SerializationError::HasherMismatch {expected: H::name(), found: hasher.to_string()});
+  }
+
+  Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeafFormatJson {
+  Claim,
+  IndexedClaim,
+}
+
+impl From<LeafFormat> for LeafFormatJson {
+  fn from(leaf_format: LeafFormat) -> Self {
+    match leaf_format {
+      LeafFormat::Claim => LeafFormatJson::Claim,
+      LeafFormat::IndexedClaim => LeafFormatJson::IndexedClaim,
+    }
+  }
+}
+
+impl From<LeafFormatJson> for LeafFormat {
+  fn from(leaf_format: LeafFormatJson) -> Self {
+    match leaf_format {
+      LeafFormatJson::Claim => LeafFormat::Claim,
+      LeafFormatJson::IndexedClaim => LeafFormat::IndexedClaim,
+    }
+  }
+}
+
+/// JSON representation of a MerkleTree. Hashes are hex encoded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTreeJson {
+  /// See `MerkleHasher::name`
+  pub hasher: String,
+  pub root: String,
+  pub leaves: Vec<String>,
+}
+
+/// JSON representation of a single claim of a Distribution. The claimant is base58 encoded, hashes are hex encoded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ClaimJson {
+  pub index: u64,
+  pub claimant: String,
+  pub amount: u64,
+  pub leaf: String,
+  pub proof: Vec<String>,
+}
+
+/// JSON representation of a Distribution that can be shipped to a frontend
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DistributionJson {
+  /// See `MerkleHasher::name`
+  pub hasher: String,
+  pub root: String,
+  pub leaf_format: LeafFormatJson,
+  pub double_hashed: bool,
+  /// Serialized as string since it might not fit into a javascript number
+  pub total_amount: String,
+  pub claims: Vec<ClaimJson>,
+}
+
+impl MerkleTree {
+  /// Rebuilds the tree from its leaves and checks that the root matches the stored one
+  pub fn from_json(json: &MerkleTreeJson) -> Result<Self, SerializationError> {
+    Self::from_json_with_hasher(json)
+  }
+
+  pub fn from_json_str(json: &str) -> Result<Self, SerializationError> {
+    Self::from_json(&serde_json::from_str(json)?)
+  }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+  pub fn to_json(&self) -> MerkleTreeJson {
+    MerkleTreeJson {
+      hasher: H::name(),
+      root: self.root().map(|root| to_hex(&root)).unwrap_or_default(),
+      leaves: self.leaves().iter().map(to_hex).collect(),
+    }
+  }
+
+  pub fn to_json_string(&self) -> String {
+    serde_json::to_string_pretty(&self.to_json()).unwrap()
+  }
+
+  /// Same as from_json for trees that use a different hasher
+  pub fn from_json_with_hasher(json: &MerkleTreeJson) -> Result<Self, SerializationError> {
+    check_hasher::<H>(&json.hasher)?;
+
+    let leaves = json.leaves
+      .iter()
+      .map(|leaf| from_hex(leaf))
+      .collect::<Result<Vec<_>, _>>()?;
+    let tree = Self::from_leaves(leaves);
+
+    if tree.root().map(|root| to_hex(&root)).unwrap_or_default() != json.root.trim_start_matches("0x") {
+      return Err(SerializationError::RootMismatch);
+    }
+
+    Ok(tree)
+  }
+
+}
+
+impl Distribution {
+  /// Rebuilds the distribution from its claims and checks that the root, as well as the leaf and proof of every
+  /// claim, match the stored ones. Claims are ordered by their index which must be contiguous starting at 0.
+  pub fn from_json(json: &DistributionJson) -> Result<Self, SerializationError> {
+    Self::from_json_with_hasher(json)
+  }
+
+  pub fn from_json_str(json: &str) -> Result<Self, SerializationError> {
+    Self::from_json(&serde_json::from_str(json)?)
+  }
+
+  /// Builds a distribution from `claimant,amount` rows where the amount is in base units.
+  /// Empty lines and an optional header row are skipped.
+  pub fn from_csv(csv: &str, leaf_format: LeafFormat, double_hashed: bool) -> Result<Self, SerializationError> {
+    Self::from_csv_with_hasher(csv, leaf_format, double_hashed)
+  }
+}
+
+impl<H: MerkleHasher> Distribution<H> {
+  pub fn to_json(&self) -> DistributionJson {
+    let claims = self.claims
+      .iter()
+      .map(|claim| ClaimJson {
+        index: claim.index,
+        claimant: claim.claimant.to_string(),
+        amount: claim.amount,
+        leaf: to_hex(&self.leaves()[claim.index as usize]),
+        proof: self.tree().proof(&[claim.index as usize]).iter().map(to_hex).collect(),
+      })
+      .collect();
+
+    DistributionJson {
+      hasher: H::name(),
+      root: self.root().map(|root| to_hex(&root)).unwrap_or_default(),
+      leaf_format: self.leaf_format.into(),
+      double_hashed: self.double_hashed,
+      total_amount: self.total_amount().to_string(),
+      claims,
+    }
+  }
+
+  pub fn to_json_string(&self) -> String {
+    serde_json::to_string_pretty(&self.to_json()).unwrap()
+  }
+
+  /// Same as from_json for distributions that use a different hasher
+  pub fn from_json_with_hasher(json: &DistributionJson) -> Result<Self, SerializationError> {
+    check_hasher::<H>(&json.hasher)?;
+
+    let mut claims = json.claims.iter().collect::<Vec<_>>();
+    claims.sort_by_key(|claim| claim.index);
+
+    for (expected, claim) in claims.iter().enumerate() {
+      if claim.index != expected as u64 {
+        return Err(SerializationError::NonContiguousIndex {expected: expected as u64, found: claim.index});
+      }
+    }
+
+    let claimants = claims
+      .iter()
+      .map(|claim| Ok((parse_pubkey(&claim.claimant)?, claim.amount)))
+      .collect::<Result<Vec<_>, SerializationError>>()?;
+    let distribution = Self::with_hasher(&claimants, json.leaf_format.into(), json.double_hashed);
+
+    if distribution.root().map(|root| to_hex(&root)).unwrap_or_default() != json.root.trim_start_matches("0x") {
+      return Err(SerializationError::RootMismatch);
+    }
+
+    for claim in claims {
+      let index = claim.index as usize;
+
+      if from_hex(&claim.leaf)? != distribution.leaves()[index] {
+        return Err(SerializationError::LeafMismatch(claim.index));
+      }
+
+      let proof = claim.proof.iter().map(|hash| from_hex(hash)).collect::<Result<Vec<_>, _>>()?;
+      if proof != distribution.tree().proof(&[index]) {
+        return Err(SerializationError::ProofMismatch(claim.index));
+      }
+    }
+
+    Ok(distribution)
+  }
+
+  /// Same as from_csv for distributions that use a different hasher
+  pub fn from_csv_with_hasher(
+    csv: &str,
+    leaf_format: LeafFormat,
+    double_hashed: bool,
+  ) -> Result<Self, SerializationError> {
+    let mut claims = vec![];
+    let mut is_first_row = true;
+
+    for (i, row) in csv.lines().enumerate() {
+      let line = i + 1;
+      let row = row.trim();
+
+      if row.is_empty() {
+        continue;
+      }
+
+      let is_header_candidate = is_first_row;
+      is_first_row = false;
+
+      let columns = row.split(',').map(|column| column.trim()).collect::<Vec<_>>();
+      if columns.len() != 2 {
+        return Err(SerializationError::Csv(line, format!("expected 2 columns found {}", columns.len())));
+      }
+
+      let amount = match columns[1].parse::<u64>() {
+        Ok(amount) => amount,
+        // header row
+        Err(_) if is_header_candidate && Pubkey::from_str(columns[0]).is_err() => continue,
+        Err(_) => return Err(SerializationError::Csv(line, format!("invalid amount {}", columns[1]))),
+      };
+
+      let claimant = Pubkey::from_str(columns[0])
+        .map_err(|_| SerializationError::Csv(line, format!("invalid pubkey {}", columns[0])))?;
+
+      claims.push((claimant, amount));
+    }
+
+    Ok(Self::with_hasher(&claims, leaf_format, double_hashed))
+  }
+}
@@ -1,27 +1,99 @@
+use std::{
+  collections::HashSet,
+  time::{Duration, SystemTime},
+};
 use solana_program_runtime::invoke_context::ProcessInstructionWithContext;
 use solana_sdk::{
   rent::{Rent},
   system_instruction,
   program_error::ProgramError,
-  clock::{Clock, UnixTimestamp},
+  clock::{Clock, UnixTimestamp, Epoch, Slot},
+  epoch_schedule::EpochSchedule,
+  sysvar::{Sysvar, SysvarId},
   account::AccountSharedData,
   pubkey::Pubkey,
-  signature::{Keypair, Signer},
+  signature::{Keypair, Signer, Signature},
+  signer::signers::Signers,
   borsh::{try_from_slice_unchecked},
-  transaction::Transaction,
-  instruction::Instruction,
+  transaction::{Transaction, TransactionError, VersionedTransaction},
+  instruction::{Instruction, AccountMeta},
+  system_program,
+  message::{v0, VersionedMessage},
+  address_lookup_table_account::AddressLookupTableAccount,
+  slot_hashes::{SlotHashes, MAX_ENTRIES as MAX_SLOT_HASHES},
+  packet::PACKET_DATA_SIZE,
+  hash::Hash,
+  nonce::{self, state::{Data as NonceData, Versions as NonceVersions}},
+};
+use solana_address_lookup_table_program::{
+  instruction::{
+    create_lookup_table, extend_lookup_table, freeze_lookup_table, deactivate_lookup_table, close_lookup_table,
+  },
+  state::AddressLookupTable,
 };
-use solana_program_test::{ProgramTestContext};
+use solana_program_test::{ProgramTestContext, ProgramTestBanksClientExt, BanksClientError};
 use crate::{
-  time::{get_clock, advance_clock_past_timestamp, advance_clock_by_slots},
-  tools::{clone_keypair, map_transaction_error}
+  time::{
+    get_clock, set_clock, set_unix_timestamp, advance_clock_past_timestamp, advance_clock_by_slots,
+    advance_clock_by_min_timespan, advance_time, advance_to, advance_to_timestamp, advance_to_epoch, advance_epochs,
+    get_epoch_schedule, set_epoch_schedule, warp_to_slot, first_slot_in_epoch,
+  },
+  tools::{clone_keypair, map_transaction_error, pack_instructions},
+  keypair::KeypairFactory,
+  logs::{LogPrinting, TransactionLogs},
 };
+use regex::Regex;
+
+/// Number of addresses sent in each extend_lookup_table transaction
+const LOOKUP_TABLE_EXTEND_CHUNK: usize = 20;
+
+/// Number of transactions whose logs are kept when capture_logs is enabled
+const DEFAULT_MAX_TRANSACTION_LOGS: usize = 100;
+
+/// What process_transaction does when the transaction it is about to send is identical to one it has already
+/// processed, e.g. the same instruction sent twice within the same blockhash. The bank would silently skip
+/// such a transaction and report the status of the earlier one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateTransactionStrategy {
+  /// Waits until the bank produces a new blockhash and signs the transaction again. Nothing else changes.
+  #[default]
+  NewBlockhash,
+  /// Warps one slot forward which makes a new blockhash available immediately but also moves the Clock
+  AdvanceSlot,
+  /// Prepends an SPL Memo instruction with a unique counter so that the signature differs. The memo touches no
+  /// account and only consumes its own few hundred compute units, so the rest of the transaction runs as before.
+  /// Note! If the transaction sets no compute unit limit, the default limit grows by one instruction's worth.
+  /// Requires the SPL Memo program. solana-program-test bundles a BPF build of it which can not be loaded in every
+  /// environment; add the native one with `processor!(spl_memo::processor::process_instruction)` in that case.
+  MemoNonce,
+}
 
 pub struct ProgramTest {
   pub context: ProgramTestContext,
   pub rent: Rent,
   pub payer: Keypair,
   pub next_id: u8,
+  pub lamports_per_signature: u64,
+  /// Total fees paid by the payer for all transactions sent through `process_transaction`
+  pub fees_paid: u64,
+  /// Source of the keypairs created by this crate. Replace it with `KeypairFactory::from_seed` (or use
+  /// `start_with_seed`) to get the same addresses on every run.
+  pub keypairs: KeypairFactory,
+  pub duplicate_transaction_strategy: DuplicateTransactionStrategy,
+  /// Number of memo nonces used so far, which makes each of them unique
+  memo_nonce: u32,
+  /// Signatures of the transactions sent through process_transaction, used to detect duplicates without asking
+  /// the bank. Transactions sent directly through the BanksClient are not included.
+  processed_signatures: HashSet<Signature>,
+  /// Records the logs of every transaction in transaction_logs. Off by default since BanksClient only returns
+  /// logs for simulations, so each transaction is simulated right before it is processed.
+  pub capture_logs: bool,
+  /// Only the latest transactions are kept in transaction_logs
+  pub max_transaction_logs: usize,
+  /// Any setting but Never simulates every transaction as capture_logs does, so the printed logs come from the
+  /// same bank state the transaction is then processed with
+  pub log_printing: LogPrinting,
+  pub transaction_logs: Vec<TransactionLogs>,
 }
 
 impl ProgramTest {
@@ -29,15 +101,48 @@ impl ProgramTest {
     let mut context = program_test.start_with_context().await;
     let rent = context.banks_client.get_rent().await.unwrap();
     let payer = clone_keypair(&context.payer);
+    #[allow(deprecated)]
+    let (fee_calculator, _, _) = context.banks_client.get_fees().await.unwrap();
 
     Self {
       context,
       rent,
       payer,
       next_id: 0,
+      lamports_per_signature: fee_calculator.lamports_per_signature,
+      fees_paid: 0,
+      keypairs: KeypairFactory::random(),
+      duplicate_transaction_strategy: DuplicateTransactionStrategy::default(),
+      memo_nonce: 0,
+      processed_signatures: HashSet::new(),
+      capture_logs: false,
+      max_transaction_logs: DEFAULT_MAX_TRANSACTION_LOGS,
+      log_printing: LogPrinting::default(),
+      transaction_logs: vec![],
     }
   }
 
+  /// Same as start_new but all the keypairs created through this crate are derived from the given seed
+  pub async fn start_with_seed(program_test: solana_program_test::ProgramTest, seed: u64) -> Self {
+    let mut pt = Self::start_new(program_test).await;
+    pt.keypairs = KeypairFactory::from_seed(seed);
+
+    pt
+  }
+
+  /// Same as start_new but overrides the EpochSchedule sysvar so that epoch boundaries can be reached quickly.
+  /// Note! Only the sysvar view changes, the bank's epochs, stake activation and rewards keep following the genesis
+  /// schedule; see `time::set_epoch_schedule`.
+  pub async fn start_with_epoch_schedule(
+    program_test: solana_program_test::ProgramTest,
+    epoch_schedule: EpochSchedule,
+  ) -> Self {
+    let mut pt = Self::start_new(program_test).await;
+    pt.set_epoch_schedule(&epoch_schedule).await;
+
+    pt
+  }
+
   pub fn add_program(
     program_test: &mut solana_program_test::ProgramTest,
     program_name: &str,
@@ -47,18 +152,216 @@ impl ProgramTest {
     program_test.add_program(program_name, program_id, process_instruction);
   }
 
+  /// Sends a transaction paid and signed by the payer along with any additional signers
   pub async fn process_transaction(
     &mut self,
     instructions: &[Instruction],
     signers: Option<&[&Keypair]>,
   ) -> Result<(), ProgramError> {
-    let mut transaction = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
-    let mut all_signers = vec![&self.payer];
+    let payer = clone_keypair(&self.payer);
+    let mut all_signers: Vec<&dyn Signer> = vec![&payer];
 
     if let Some(signers) = signers {
-      all_signers.extend_from_slice(signers);
+      all_signers.extend(signers.iter().map(|signer| *signer as &dyn Signer));
+    }
+
+    self.process_transaction_with_payer(instructions, &payer.pubkey(), &all_signers).await
+  }
+
+  /// Sends a transaction whose fees are paid by `fee_payer`, which must be one of the signers. Any `Signers`
+  /// implementation is accepted. Duplicate signers, as well as signers the transaction does not require,
+  /// are ignored, so e.g. the payer can always be passed even if the instructions do not need its signature.
+  /// Only fees paid by the ProgramTest payer are added to fees_paid.
+  pub async fn process_transaction_with_payer<S: Signers + ?Sized>(
+    &mut self,
+    instructions: &[Instruction],
+    fee_payer: &Pubkey,
+    signers: &S,
+  ) -> Result<(), ProgramError> {
+    let mut recent_blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_instructions = instructions.to_vec();
+
+    let transaction = loop {
+      let mut transaction = Transaction::new_with_payer(&all_instructions, Some(fee_payer));
+      transaction.message.recent_blockhash = recent_blockhash;
+
+      let num_required_signatures = transaction.message.header.num_required_signatures as usize;
+      transaction.signatures = Self::sign_message(
+        &transaction.message_data(),
+        &transaction.message.account_keys[..num_required_signatures],
+        signers,
+      );
+
+      if !self.processed_signatures.contains(&transaction.signatures[0]) {
+        break transaction;
+      }
+
+      match self.duplicate_transaction_strategy {
+        DuplicateTransactionStrategy::NewBlockhash => recent_blockhash = self.new_blockhash(&recent_blockhash).await,
+        DuplicateTransactionStrategy::AdvanceSlot => {
+          self.advance_clock_by_slots(1).await;
+          recent_blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+        }
+        DuplicateTransactionStrategy::MemoNonce => {
+          self.memo_nonce += 1;
+          let memo = format!("nonce {}", self.memo_nonce);
+
+          all_instructions = vec![spl_memo::build_memo(memo.as_bytes(), &[])];
+          all_instructions.extend_from_slice(instructions);
+        }
+      }
+    };
+
+    let fee = self.lamports_per_signature * transaction.signatures.len() as u64;
+    self.processed_signatures.insert(transaction.signatures[0]);
+    let result = self.process_and_log(transaction)
+      .await
+      .map_err(|e| map_transaction_error(e.into()));
+
+    // Note! map_transaction_error panics on anything but instruction errors so the fee has been charged at this point
+    if *fee_payer == self.payer.pubkey() {
+      self.fees_paid += fee;
+    }
+
+    result
+  }
+
+  /// Processes the transaction and captures or prints its logs as configured. BanksClient only returns logs for
+  /// simulations, so the transaction is simulated right before it is processed, against the same bank state.
+  async fn process_and_log(&mut self, transaction: Transaction) -> Result<(), BanksClientError> {
+    let logs = match self.capture_logs || self.log_printing != LogPrinting::Never {
+      true => Some(self.simulate_logs(&transaction).await),
+      false => None,
+    };
+
+    let result = self.context
+      .banks_client
+      .process_transaction(transaction)
+      .await;
+
+    if let Some(logs) = logs {
+      self.handle_logs(logs, result.is_err());
+    }
+
+    result
+  }
+
+  async fn simulate_logs(&mut self, transaction: &Transaction) -> TransactionLogs {
+    let simulation = self.context
+      .banks_client
+      .simulate_transaction(transaction.clone())
+      .await
+      .unwrap();
+    let (logs, units_consumed) = simulation.simulation_details
+      .map(|details| (details.logs, details.units_consumed))
+      .unwrap_or_default();
+
+    TransactionLogs {
+      signature: transaction.signatures[0],
+      logs,
+      result: simulation.result.unwrap_or(Ok(())),
+      units_consumed,
+    }
+  }
+
+  fn handle_logs(&mut self, logs: TransactionLogs, is_failure: bool) {
+    let should_print = match self.log_printing {
+      LogPrinting::Never => false,
+      LogPrinting::OnFailure => is_failure,
+      LogPrinting::Always => true,
+    };
+
+    if should_print {
+      logs.print();
+    }
+
+    if self.capture_logs {
+      self.transaction_logs.push(logs);
+
+      let excess = self.transaction_logs.len().saturating_sub(self.max_transaction_logs);
+      self.transaction_logs.drain(..excess);
+    }
+  }
+
+  /// Logs of the latest transaction
+  pub fn last_logs(&self) -> &TransactionLogs {
+    self.transaction_logs
+      .last()
+      .expect("LOGS-ERROR: no logs have been captured; is capture_logs enabled?")
+  }
+
+  pub fn clear_logs(&mut self) {
+    self.transaction_logs.clear();
+  }
+
+  /// Panics, printing the logs, unless a line of the latest transaction's logs contains the given text
+  pub fn assert_log_contains(&self, needle: &str) {
+    let logs = self.last_logs();
+
+    if !logs.contains(needle) {
+      logs.print();
+      panic!("ASSERT-LOG-ERROR: no log contains {:?}", needle);
+    }
+  }
+
+  /// Same as assert_log_contains for a regular expression e.g. `pt.assert_log_matches(r"amount: \d+")`
+  pub fn assert_log_matches(&self, pattern: &str) {
+    let logs = self.last_logs();
+    let regex = Regex::new(pattern).unwrap_or_else(|e| panic!("ASSERT-LOG-ERROR: invalid pattern {}", e));
+
+    if !logs.matches(&regex) {
+      logs.print();
+      panic!("ASSERT-LOG-ERROR: no log matches {:?}", pattern);
     }
+  }
+
+  /// Same as assert_log_contains but only considers the messages logged by the given program itself
+  pub fn assert_program_log_contains(&self, program_id: &Pubkey, needle: &str) {
+    let logs = self.last_logs();
+
+    if !logs.logs_of(program_id).iter().any(|message| message.contains(needle)) {
+      logs.print();
+      panic!("ASSERT-LOG-ERROR: no log of program {} contains {:?}", program_id, needle);
+    }
+  }
+
+  /// Waits for the bank to produce a blockhash other than the given one. If that takes more than 5 seconds,
+  /// a slot is warped instead.
+  pub async fn new_blockhash(&mut self, blockhash: &Hash) -> Hash {
+    match self.context.banks_client.get_new_latest_blockhash(blockhash).await {
+      Ok(new_blockhash) => new_blockhash,
+      Err(_) => {
+        self.advance_clock_by_slots(1).await;
+        self.context.banks_client.get_latest_blockhash().await.unwrap()
+      }
+    }
+  }
 
+  /// Returns the signatures of the given signer keys in order. Signers that are not required are ignored.
+  fn sign_message<S: Signers + ?Sized>(message_data: &[u8], signer_keys: &[Pubkey], signers: &S) -> Vec<Signature> {
+    let mut signatures = vec![Signature::default(); signer_keys.len()];
+    let signer_signatures = signers.try_sign_message(message_data).unwrap();
+
+    for (pubkey, signature) in signers.pubkeys().iter().zip(signer_signatures) {
+      if let Some(position) = signer_keys.iter().position(|key| key == pubkey) {
+        signatures[position] = signature;
+      }
+    }
+
+    if let Some(position) = signatures.iter().position(|signature| *signature == Signature::default()) {
+      panic!("PROCESS-TRANSACTION-ERROR: missing signer {}", signer_keys[position]);
+    }
+
+    signatures
+  }
+
+  /// Builds and signs a v0 transaction paid by the payer that loads accounts through the given lookup tables
+  pub async fn create_versioned_transaction(
+    &mut self,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    signers: Option<&[&Keypair]>,
+  ) -> VersionedTransaction {
     let recent_blockhash = self
       .context
       .banks_client
@@ -66,19 +369,429 @@ impl ProgramTest {
       .await
       .unwrap();
 
-    transaction.sign(&all_signers, recent_blockhash);
+    let message = v0::Message::try_compile(&self.payer.pubkey(), instructions, lookup_tables, recent_blockhash)
+      .unwrap_or_else(|e| panic!("CREATE-VERSIONED-TRANSACTION-ERROR: {}", e));
+    let num_required_signatures = message.header.num_required_signatures as usize;
+    let signer_keys = message.account_keys[..num_required_signatures].to_vec();
+    let message = VersionedMessage::V0(message);
+
+    let mut all_signers: Vec<&dyn Signer> = vec![&self.payer];
+    if let Some(signers) = signers {
+      all_signers.extend(signers.iter().map(|signer| *signer as &dyn Signer));
+    }
+
+    VersionedTransaction {
+      signatures: Self::sign_message(&message.serialize(), &signer_keys, &all_signers),
+      message,
+    }
+  }
+
+  /// Legacy emulation of a v0 transaction; this is not versioned transaction support. The instructions are
+  /// compiled into a v0 transaction that uses the given lookup tables, which is checked the way the runtime
+  /// would load it, and then the same instructions are executed as a legacy transaction with the same signers.
+  /// It panics if the transaction does not fit into a packet, if a table does not exist, is not active or does
+  /// not hold the given addresses at the current slot e.g. the table was deactivated and its cool down is over,
+  /// or addresses were appended in the current slot. Signers and invoked programs are never loaded from a table
+  /// even if a table holds their address, the same as on a cluster.
+  ///
+  /// Note! The BanksClient of solana 1.14 only accepts legacy transactions, so the v0 message is never executed.
+  /// The legacy transaction orders its accounts differently: v0 puts the looked up accounts after all the static
+  /// ones, writable before readonly. Programs see the accounts of their instruction in the same order either way,
+  /// but anything that depends on transaction level account indexes, e.g. instruction introspection through the
+  /// Instructions sysvar, sees the legacy layout. The bank does not enforce the packet size for legacy
+  /// transactions, so fees and the remaining program behaviour are the same as for the v0 transaction.
+  pub async fn emulate_v0_transaction(
+    &mut self,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    signers: Option<&[&Keypair]>,
+  ) -> Result<(), ProgramError> {
+    let transaction = self.create_versioned_transaction(instructions, lookup_tables, signers).await;
+    let size = bincode::serialized_size(&transaction).unwrap() as usize;
+
+    if size > PACKET_DATA_SIZE {
+      panic!("EMULATE-V0-TRANSACTION-ERROR: transaction size {} exceeds {}", size, PACKET_DATA_SIZE);
+    }
+
+    if let VersionedMessage::V0(message) = &transaction.message {
+      let current_slot = self.get_clock().await.slot;
+      // Warping keeps old entries in SlotHashes while on a cluster it only holds the slots of the last
+      // MAX_ENTRIES blocks, so the cool down of a deactivated table would never end
+      let slot_hashes = self.get_sysvar::<SlotHashes>().await
+        .iter()
+        .filter(|(slot, _)| slot.saturating_add(MAX_SLOT_HASHES as u64) >= current_slot)
+        .copied()
+        .collect::<Vec<_>>();
+      let slot_hashes = SlotHashes::new(&slot_hashes);
+
+      for table_lookup in &message.address_table_lookups {
+        let lookup_table = &table_lookup.account_key;
+        let account = self.context.banks_client.get_account(*lookup_table).await.unwrap()
+          .filter(|account| account.owner == solana_address_lookup_table_program::id())
+          .unwrap_or_else(|| panic!("EMULATE-V0-TRANSACTION-ERROR: lookup table {} not found", lookup_table));
+        let stored = AddressLookupTable::deserialize(&account.data).unwrap();
+
+        // the same resolution the runtime does when it loads the transaction
+        let indexes = [&table_lookup.writable_indexes[..], &table_lookup.readonly_indexes[..]].concat();
+        let addresses = stored
+          .lookup(current_slot, &indexes, &slot_hashes)
+          .unwrap_or_else(|e| panic!("EMULATE-V0-TRANSACTION-ERROR: lookup table {}: {}", lookup_table, e));
+
+        let expected = lookup_tables
+          .iter()
+          .find(|table| table.key == *lookup_table)
+          .unwrap();
+        let matches_bank = indexes
+          .iter()
+          .zip(addresses)
+          .all(|(index, address)| expected.addresses[*index as usize] == address);
+
+        if !matches_bank {
+          panic!("EMULATE-V0-TRANSACTION-ERROR: lookup table {} does not match the bank", lookup_table);
+        }
+      }
+    }
+
+    self.process_transaction(instructions, signers).await
+  }
+
+  /// Creates an address lookup table owned by the given authority. The table address is derived from the most
+  /// recent slot in the SlotHashes sysvar.
+  pub async fn create_lookup_table(&mut self, authority: &Keypair) -> Pubkey {
+    let (mut create_ix, mut lookup_table) = self.create_lookup_table_ix(authority).await;
+
+    // the same authority already created a table from this slot so move to the next one
+    if self.context.banks_client.get_account(lookup_table).await.unwrap().is_some() {
+      self.advance_clock_by_slots(1).await;
+      (create_ix, lookup_table) = self.create_lookup_table_ix(authority).await;
+    }
+
+    self.process_transaction(&[create_ix], Some(&[authority]))
+      .await
+      .unwrap();
+
+    lookup_table
+  }
+
+  async fn create_lookup_table_ix(&mut self, authority: &Keypair) -> (Instruction, Pubkey) {
+    let slot_hashes = self.get_sysvar::<SlotHashes>().await;
+    let recent_slot = slot_hashes
+      .first()
+      .map(|(slot, _)| *slot)
+      .expect("CREATE-LOOKUP-TABLE-ERROR: SlotHashes is empty");
+
+    create_lookup_table(authority.pubkey(), self.payer.pubkey(), recent_slot)
+  }
+
+  /// Appends the addresses to the table sending as many transactions as needed. The bank is then warped
+  /// by one slot since addresses added in the current slot can not be used yet.
+  pub async fn extend_lookup_table(&mut self, lookup_table: &Pubkey, authority: &Keypair, addresses: &[Pubkey]) {
+    for chunk in addresses.chunks(LOOKUP_TABLE_EXTEND_CHUNK) {
+      let extend_ix = extend_lookup_table(
+        *lookup_table,
+        authority.pubkey(),
+        Some(self.payer.pubkey()),
+        chunk.to_vec(),
+      );
+
+      self.process_transaction(&[extend_ix], Some(&[authority]))
+        .await
+        .unwrap();
+    }
+
+    self.advance_clock_by_slots(1).await;
+  }
+
+  /// Creates a table with the given addresses that is ready to be used in a v0 transaction
+  pub async fn create_lookup_table_with_addresses(
+    &mut self,
+    authority: &Keypair,
+    addresses: &[Pubkey],
+  ) -> AddressLookupTableAccount {
+    let lookup_table = self.create_lookup_table(authority).await;
+    self.extend_lookup_table(&lookup_table, authority, addresses).await;
+
+    self.get_lookup_table(&lookup_table).await
+  }
+
+  pub async fn get_lookup_table(&mut self, lookup_table: &Pubkey) -> AddressLookupTableAccount {
+    let account = self.context.banks_client.get_account(*lookup_table).await.unwrap()
+      .unwrap_or_else(|| panic!("GET-LOOKUP-TABLE-ERROR: Account {}", lookup_table));
+    let table = AddressLookupTable::deserialize(&account.data).unwrap();
+
+    AddressLookupTableAccount {
+      key: *lookup_table,
+      addresses: table.addresses.to_vec(),
+    }
+  }
+
+  pub async fn freeze_lookup_table(&mut self, lookup_table: &Pubkey, authority: &Keypair) {
+    let freeze_ix = freeze_lookup_table(*lookup_table, authority.pubkey());
+
+    self.process_transaction(&[freeze_ix], Some(&[authority]))
+      .await
+      .unwrap();
+  }
+
+  pub async fn deactivate_lookup_table(&mut self, lookup_table: &Pubkey, authority: &Keypair) {
+    let deactivate_ix = deactivate_lookup_table(*lookup_table, authority.pubkey());
+
+    self.process_transaction(&[deactivate_ix], Some(&[authority]))
+      .await
+      .unwrap();
+  }
+
+  /// Closes a deactivated table. This is only possible once the deactivation slot is no longer in SlotHashes,
+  /// which holds the last 512 slots the bank was at. Each warp adds a single entry, so the bank is warped one slot
+  /// at a time until the cool down is over, which takes several seconds if the table was just deactivated.
+  pub async fn close_lookup_table(&mut self, lookup_table: &Pubkey, authority: &Keypair, recipient: &Pubkey) {
+    let account = self.context.banks_client.get_account(*lookup_table).await.unwrap()
+      .unwrap_or_else(|| panic!("GET-LOOKUP-TABLE-ERROR: Account {}", lookup_table));
+    let deactivation_slot = AddressLookupTable::deserialize(&account.data).unwrap().meta.deactivation_slot;
+
+    loop {
+      let current_slot = self.get_clock().await.slot;
+      let slot_hashes = self.get_sysvar::<SlotHashes>().await;
+
+      if deactivation_slot != current_slot && slot_hashes.get(&deactivation_slot).is_none() {
+        break;
+      }
+
+      self.warp_to_slot(current_slot + 1).await;
+    }
+
+    let close_ix = close_lookup_table(*lookup_table, authority.pubkey(), *recipient);
+    self.process_transaction(&[close_ix], Some(&[authority]))
+      .await
+      .unwrap();
+  }
+
+  /// Creates a durable nonce account controlled by the given authority
+  pub async fn create_nonce_account(&mut self, authority: &Pubkey) -> Keypair {
+    let nonce_account = self.keypairs.next_keypair();
+    let lamports = self.rent_exempt_balance(nonce::State::size());
+    let instructions = system_instruction::create_nonce_account(
+      &self.payer.pubkey(),
+      &nonce_account.pubkey(),
+      authority,
+      lamports,
+    );
+
+    self.process_transaction(&instructions, Some(&[&nonce_account]))
+      .await
+      .unwrap();
+
+    nonce_account
+  }
+
+  pub async fn get_nonce_data(&mut self, nonce_account: &Pubkey) -> NonceData {
+    let account = self.context.banks_client.get_account(*nonce_account).await.unwrap()
+      .unwrap_or_else(|| panic!("GET-NONCE-ERROR: Account {}", nonce_account));
+
+    match bincode::deserialize::<NonceVersions>(&account.data).unwrap().state() {
+      nonce::State::Initialized(data) => data.clone(),
+      nonce::State::Uninitialized => panic!("GET-NONCE-ERROR: {} is not initialized", nonce_account),
+    }
+  }
+
+  /// The value that transactions using the nonce account must set as their recent blockhash
+  pub async fn get_nonce(&mut self, nonce_account: &Pubkey) -> Hash {
+    self.get_nonce_data(nonce_account).await.blockhash()
+  }
+
+  /// Stores a new nonce. This fails with SystemError::NonceBlockhashNotExpired if the nonce has already been
+  /// advanced with the current blockhash; call `advance_clock_by_slots(1)` first to get a new one.
+  pub async fn advance_nonce(&mut self, nonce_account: &Pubkey, authority: &Keypair) -> Result<(), ProgramError> {
+    let advance_ix = system_instruction::advance_nonce_account(nonce_account, &authority.pubkey());
+    self.process_transaction(&[advance_ix], Some(&[authority])).await
+  }
+
+  /// Builds a transaction that uses the stored nonce instead of a recent blockhash, so it can be signed now and
+  /// sent at any point later with `process_nonce_transaction`. The advance nonce instruction is prepended to
+  /// the given instructions.
+  pub async fn create_nonce_transaction<S: Signers + ?Sized>(
+    &mut self,
+    instructions: &[Instruction],
+    nonce_account: &Pubkey,
+    nonce_authority: &Pubkey,
+    fee_payer: &Pubkey,
+    signers: &S,
+  ) -> Transaction {
+    let mut all_instructions = vec![system_instruction::advance_nonce_account(nonce_account, nonce_authority)];
+    all_instructions.extend_from_slice(instructions);
+
+    let mut transaction = Transaction::new_with_payer(&all_instructions, Some(fee_payer));
+    transaction.message.recent_blockhash = self.get_nonce(nonce_account).await;
+
+    let num_required_signatures = transaction.message.header.num_required_signatures as usize;
+    transaction.signatures = Self::sign_message(
+      &transaction.message_data(),
+      &transaction.message.account_keys[..num_required_signatures],
+      signers,
+    );
+
+    transaction
+  }
+
+  /// Sends the instructions using the nonce account instead of the latest blockhash. The payer pays the fees.
+  pub async fn process_transaction_with_nonce(
+    &mut self,
+    instructions: &[Instruction],
+    nonce_account: &Pubkey,
+    nonce_authority: &Keypair,
+    signers: Option<&[&Keypair]>,
+  ) -> Result<(), TransactionError> {
+    let payer = clone_keypair(&self.payer);
+    let mut all_signers: Vec<&dyn Signer> = vec![&payer, nonce_authority];
+
+    if let Some(signers) = signers {
+      all_signers.extend(signers.iter().map(|signer| *signer as &dyn Signer));
+    }
+
+    let transaction = self.create_nonce_transaction(
+      instructions,
+      nonce_account,
+      &nonce_authority.pubkey(),
+      &payer.pubkey(),
+      &all_signers,
+    ).await;
+
+    self.process_nonce_transaction(&transaction, &all_signers).await
+  }
+
+  /// Sends a transaction created by `create_nonce_transaction`. Unlike process_transaction every error is
+  /// returned instead of panicking, since e.g. an already used nonce fails with TransactionError::BlockhashNotFound.
+  /// The signed transaction is verified and simulated as is, which runs the same signature and nonce checks as the
+  /// cluster and produces its logs. It can not be executed as is though, see `resign_nonce_transaction`, so
+  /// `signers` must hold every signer of the transaction. If an instruction fails, the error is returned and, as
+  /// on a cluster, the fee is charged and the nonce advanced.
+  pub async fn process_nonce_transaction<S: Signers + ?Sized>(
+    &mut self,
+    transaction: &Transaction,
+    signers: &S,
+  ) -> Result<(), TransactionError> {
+    transaction.verify()?;
+    let simulation = self.simulate_logs(transaction).await;
+
+    match simulation.result.clone() {
+      Err(e @ TransactionError::InstructionError(_, _)) => {
+        self.handle_logs(simulation, true);
+        self.apply_failed_nonce_transaction(transaction, signers).await;
+
+        return Err(e);
+      }
+      Err(e) => return Err(e),
+      Ok(()) => {}
+    }
+
+    let fee_payer = transaction.message.account_keys[0];
+    let instruction_count = transaction.message.instructions.len();
+    let resigned = self.resign_nonce_transaction(transaction, instruction_count, signers).await;
+
+    let fee = self.lamports_per_signature * resigned.signatures.len() as u64;
+    let result = self.context
+      .banks_client
+      .process_transaction(resigned)
+      .await
+      .map_err(|e| e.unwrap());
+    self.handle_logs(simulation, result.is_err());
+
+    // fees are charged if the transaction was executed even if one of its instructions failed
+    let is_executed = matches!(result, Ok(_) | Err(TransactionError::InstructionError(_, _)));
+    if is_executed && fee_payer == self.payer.pubkey() {
+      self.fees_paid += fee;
+    }
+
+    result
+  }
+
+  /// Does what the cluster does when an instruction of a durable nonce transaction fails, i.e. the nonce is
+  /// advanced and the fee payer pays the fee of the whole transaction, while every other change is discarded.
+  /// Note! The bank only executes the advance instruction and charges the fee of its signatures. The rest of the
+  /// fee is written directly into the fee payer's account rather than charged by the bank, so it is not collected
+  /// anywhere and the bank's capitalization drifts by that amount.
+  async fn apply_failed_nonce_transaction<S: Signers + ?Sized>(&mut self, transaction: &Transaction, signers: &S) {
+    let fee_payer = transaction.message.account_keys[0];
+    let advance_transaction = self.resign_nonce_transaction(transaction, 1, signers).await;
+    let advance_fee = self.lamports_per_signature * advance_transaction.signatures.len() as u64;
 
     self.context
       .banks_client
-      .process_transaction(transaction)
+      .process_transaction(advance_transaction)
       .await
-      .map_err(|e| map_transaction_error(e.into()))?;
+      .unwrap_or_else(|e| panic!("PROCESS-NONCE-TRANSACTION-ERROR: could not advance the nonce {}", e));
 
-    Ok(())
+    // the advance transaction has fewer signatures than the failed one, so the rest of the fee is charged directly
+    let fee = self.lamports_per_signature * transaction.signatures.len() as u64;
+    let mut account = self.context.banks_client.get_account(fee_payer).await.unwrap().unwrap();
+    account.lamports = account.lamports
+      .checked_sub(fee - advance_fee)
+      .unwrap_or_else(|| panic!(
+        "PROCESS-NONCE-TRANSACTION-ERROR: fee payer {} has {} lamports left which does not cover the remaining fee of {}",
+        fee_payer,
+        account.lamports,
+        fee - advance_fee,
+      ));
+    self.context.set_account(&fee_payer, &account.into());
+
+    if fee_payer == self.payer.pubkey() {
+      self.fees_paid += fee;
+    }
+  }
+
+  /// Note! The BanksServer of solana 1.14 unwraps the last valid block height of a transaction's recent blockhash,
+  /// so it panics, and the BanksClient hangs, on a durable nonce which is never in the blockhash queue. This is the
+  /// one place that works around it: the first `instruction_count` instructions of the already verified transaction
+  /// are signed again by `signers` with the latest blockhash and the same fee payer. The bank then executes the same
+  /// instructions with the same signers and fee, but the signatures are not the ones of the given transaction.
+  async fn resign_nonce_transaction<S: Signers + ?Sized>(
+    &mut self,
+    transaction: &Transaction,
+    instruction_count: usize,
+    signers: &S,
+  ) -> Transaction {
+    let message = &transaction.message;
+    let signer_keys = signers.pubkeys();
+
+    if let Some(missing) = message.signer_keys().into_iter().find(|key| !signer_keys.contains(key)) {
+      panic!("PROCESS-NONCE-TRANSACTION-ERROR: the keypair of signer {} must be passed to sign it again", missing);
+    }
+
+    let instructions = message.instructions
+      .iter()
+      .take(instruction_count)
+      .map(|instruction| Instruction {
+        program_id: message.account_keys[instruction.program_id_index as usize],
+        accounts: instruction.accounts
+          .iter()
+          .map(|index| {
+            let index = *index as usize;
+            AccountMeta {
+              pubkey: message.account_keys[index],
+              is_signer: message.is_signer(index),
+              is_writable: message.is_writable(index),
+            }
+          })
+          .collect(),
+        data: instruction.data.clone(),
+      })
+      .collect::<Vec<_>>();
+
+    let mut resigned = Transaction::new_with_payer(&instructions, Some(&message.account_keys[0]));
+    resigned.message.recent_blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+
+    let num_required_signatures = resigned.message.header.num_required_signatures as usize;
+    resigned.signatures = Self::sign_message(
+      &resigned.message_data(),
+      &resigned.message.account_keys[..num_required_signatures],
+      signers,
+    );
+
+    resigned
   }
 
   pub async fn create_account(&mut self, lamports: u64, space: u64, owner: &Pubkey) -> Keypair {
-    let account = Keypair::new();
+    let account = self.keypairs.next_keypair();
     let create_ix = system_instruction::create_account(
       &self.payer.pubkey(),
       &account.pubkey(),
@@ -94,6 +807,12 @@ impl ProgramTest {
     account
   }
 
+  /// Creates a new account funded with the minimum rent exempt balance for the given space
+  pub async fn create_rent_exempt_account(&mut self, space: u64, owner: &Pubkey) -> Keypair {
+    let lamports = self.rent_exempt_balance(space as usize);
+    self.create_account(lamports, space, owner).await
+  }
+
   pub async fn airdrop(&mut self, to_account: &Pubkey, lamports: u64) {
     let transfer_ix = system_instruction::transfer(
       &self.payer.pubkey(),
@@ -106,6 +825,28 @@ impl ProgramTest {
       .unwrap();
   }
 
+  /// Transfers lamports from the payer to each of the recipients packing as many transfers as possible
+  /// into each transaction
+  pub async fn batch_airdrop(&mut self, recipients: &[(Pubkey, u64)]) {
+    let payer = self.payer.pubkey();
+    let groups = recipients
+      .iter()
+      .map(|(recipient, lamports)| vec![system_instruction::transfer(&payer, recipient, *lamports)])
+      .collect();
+
+    for instructions in pack_instructions(&payer, &[], groups) {
+      self.process_transaction(&instructions, None)
+        .await
+        .unwrap();
+    }
+  }
+
+  /// Writes a system account with the given balance directly into the bank. No transaction is sent so neither
+  /// the payer's balance nor fees_paid change. Any existing account at the address is overwritten.
+  pub fn set_lamports(&mut self, address: &Pubkey, lamports: u64) {
+    self.set_account(address, lamports, 0, &system_program::id());
+  }
+
   pub async fn transfer_sol(
     &mut self,
     from_account: &Keypair,
@@ -143,10 +884,45 @@ impl ProgramTest {
     self.context.set_account(address, &AccountSharedData::new(lamports, space, owner));
   }
 
+  /// Reads any sysvar stored in the bank e.g. `pt.get_sysvar::<SlotHashes>().await`
+  pub async fn get_sysvar<S: Sysvar>(&mut self) -> S {
+    self.context.banks_client.get_sysvar::<S>().await.unwrap()
+  }
+
+  /// Overrides the given sysvar. Note that the Clock, SlotHashes and RecentBlockhashes sysvars are recalculated
+  /// by the bank on every new slot, while Rent and EpochSchedule keep the overridden value.
+  pub fn set_sysvar<S: SysvarId + Sysvar>(&mut self, sysvar: &S) {
+    self.context.set_sysvar(sysvar);
+
+    // keep the cached rent in sync so that account creating helpers use the overridden values
+    if S::id() == Rent::id() {
+      self.rent = bincode::deserialize(&bincode::serialize(sysvar).unwrap()).unwrap();
+    }
+  }
+
+  /// Re-reads the Rent sysvar from the bank. Only needed if the sysvar account was overwritten by other means
+  /// than set_sysvar e.g. with `context.set_account`.
+  pub async fn refresh_rent(&mut self) -> Rent {
+    self.rent = self.get_sysvar::<Rent>().await;
+    self.rent
+  }
+
+  pub fn rent_exempt_balance(&self, space: usize) -> u64 {
+    self.rent.minimum_balance(space)
+  }
+
   pub async fn get_clock(&mut self) -> Clock {
     get_clock(&mut self.context).await
   }
 
+  pub fn set_clock(&mut self, clock: &Clock) {
+    set_clock(&mut self.context, clock)
+  }
+
+  pub async fn set_unix_timestamp(&mut self, unix_timestamp: UnixTimestamp) {
+    set_unix_timestamp(&mut self.context, unix_timestamp).await
+  }
+
   pub async fn advance_clock_past_timestamp(&mut self, unix_timestamp: UnixTimestamp) {
     advance_clock_past_timestamp(&mut self.context, unix_timestamp).await
   }
@@ -154,4 +930,46 @@ impl ProgramTest {
   pub async fn advance_clock_by_slots(&mut self, slots: u64) {
     advance_clock_by_slots(&mut self.context, slots).await
   }
+
+  pub async fn advance_clock_by_min_timespan(&mut self, time_span: u64) {
+    advance_clock_by_min_timespan(&mut self.context, time_span).await
+  }
+
+  /// Advances the clock by exactly the given duration e.g. `pt.advance_time(Duration::from_secs(30 * 86400))`
+  pub async fn advance_time(&mut self, duration: Duration) {
+    advance_time(&mut self.context, duration).await
+  }
+
+  pub async fn advance_to(&mut self, time: SystemTime) {
+    advance_to(&mut self.context, time).await
+  }
+
+  pub async fn advance_to_timestamp(&mut self, unix_timestamp: UnixTimestamp) {
+    advance_to_timestamp(&mut self.context, unix_timestamp).await
+  }
+
+  pub async fn advance_epochs(&mut self, epochs: u64) {
+    advance_epochs(&mut self.context, epochs).await
+  }
+
+  pub async fn advance_to_epoch(&mut self, epoch: Epoch) {
+    advance_to_epoch(&mut self.context, epoch).await
+  }
+
+  pub async fn get_epoch_schedule(&mut self) -> EpochSchedule {
+    get_epoch_schedule(&mut self.context).await
+  }
+
+  /// Note! Only the sysvar view changes, see `time::set_epoch_schedule`
+  pub async fn set_epoch_schedule(&mut self, epoch_schedule: &EpochSchedule) {
+    set_epoch_schedule(&mut self.context, epoch_schedule).await
+  }
+
+  pub async fn first_slot_in_epoch(&mut self, epoch: Epoch) -> Slot {
+    first_slot_in_epoch(&mut self.context, epoch).await
+  }
+
+  pub async fn warp_to_slot(&mut self, slot: Slot) {
+    warp_to_slot(&mut self.context, slot).await
+  }
 }
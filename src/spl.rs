@@ -10,11 +10,13 @@ use spl_token::{
   instruction::{
     set_authority,
     sync_native,
+    close_account,
     AuthorityType,
   },
+  native_mint,
 };
 use spl_associated_token_account::{
-  instruction::create_associated_token_account,
+  instruction::{create_associated_token_account, create_associated_token_account_idempotent},
   get_associated_token_address,
 };
 use solana_sdk::{
@@ -24,11 +26,17 @@ use solana_sdk::{
   program_pack::Pack,
   program_error::ProgramError,
   account::AccountSharedData,
+  compute_budget::ComputeBudgetInstruction,
 };
 use crate::{
   program_test::ProgramTest,
+  tools::pack_instructions,
+  utils::TokenAmount,
 };
 
+/// Compute units requested by each of the batched transactions sent by `Spl::batch_airdrop`
+const BATCH_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
 pub struct Spl {
   pub program_test: Arc<Mutex<ProgramTest>>
 }
@@ -52,6 +60,34 @@ impl Spl {
     TokenAccount::try_deserialize_unchecked(&mut account.data.as_ref()).unwrap()
   }
 
+  pub async fn get_mint(&mut self, mint: Pubkey) -> Mint {
+    let mut lock_pt = self.program_test.lock().await;
+
+    let account = lock_pt
+      .context
+      .banks_client
+      .get_account(mint)
+      .await.unwrap().unwrap();
+
+    Mint::try_deserialize_unchecked(&mut account.data.as_ref()).unwrap()
+  }
+
+  /// Converts a UI amount e.g. "1.5" into a TokenAmount using the decimals of the given mint
+  pub async fn token_amount(&mut self, mint: &Pubkey, ui_amount: &str) -> TokenAmount {
+    let decimals = self.get_mint(*mint).await.decimals;
+
+    TokenAmount::parse(ui_amount, decimals)
+      .unwrap_or_else(|| panic!("INVALID-TOKEN-AMOUNT: {} with decimals {}", ui_amount, decimals))
+  }
+
+  /// Returns the balance of the given token account along with the decimals of its mint
+  pub async fn get_token_balance(&mut self, token_account: Pubkey) -> TokenAmount {
+    let account = self.get_token_account(token_account).await;
+    let decimals = self.get_mint(account.mint).await.decimals;
+
+    TokenAmount::new(account.amount, decimals)
+  }
+
   pub async fn mint_tokens(
     &mut self,
     token_mint: &Pubkey,
@@ -75,6 +111,31 @@ impl Spl {
       .unwrap();
   }
 
+  /// Same as mint_tokens but uses mint_to_checked so the decimals of the amount are validated against the mint
+  pub async fn mint_token_amount(
+    &mut self,
+    token_mint: &Pubkey,
+    token_mint_authority: &Keypair,
+    token_account: &Pubkey,
+    amount: TokenAmount,
+  ) {
+    let ix = spl_token::instruction::mint_to_checked(
+      &spl_token::id(),
+      token_mint,
+      token_account,
+      &token_mint_authority.pubkey(),
+      &[],
+      amount.amount,
+      amount.decimals,
+    )
+    .unwrap();
+
+    let mut lock_pt = self.program_test.lock().await;
+    lock_pt.process_transaction(&[ix], Some(&[token_mint_authority]))
+      .await
+      .unwrap();
+  }
+
   pub async fn transfer(
     &mut self,
     from: &Pubkey, 
@@ -96,6 +157,31 @@ impl Spl {
     lock_pt.process_transaction(&[ix], Some(&[authority])).await
   }
 
+  /// Same as transfer but uses transfer_checked so the decimals of the amount are validated against the mint
+  pub async fn transfer_token_amount(
+    &mut self,
+    mint: &Pubkey,
+    from: &Pubkey,
+    to: &Pubkey,
+    authority: &Keypair,
+    amount: TokenAmount,
+  ) -> Result<(), ProgramError> {
+    let ix = spl_token::instruction::transfer_checked(
+      &spl_token::id(),
+      from,
+      mint,
+      to,
+      &authority.pubkey(),
+      &[&authority.pubkey()],
+      amount.amount,
+      amount.decimals,
+    )
+    .unwrap();
+
+    let mut lock_pt = self.program_test.lock().await;
+    lock_pt.process_transaction(&[ix], Some(&[authority])).await
+  }
+
   pub async fn create_mint(
     &mut self,
     mint_keypair: &Keypair,
@@ -104,7 +190,7 @@ impl Spl {
     decimals: u8,
   ) {
     let mut lock_pt = self.program_test.lock().await;
-    let mint_rent = lock_pt.rent.minimum_balance(spl_token::state::Mint::LEN);
+    let mint_rent = lock_pt.rent_exempt_balance(spl_token::state::Mint::LEN);
 
     let instructions = [
       system_instruction::create_account(
@@ -129,6 +215,19 @@ impl Spl {
       .unwrap();
   }
 
+  /// Same as create_mint but the mint keypair is generated by the ProgramTest's KeypairFactory
+  pub async fn create_new_mint(
+    &mut self,
+    mint_authority: &Pubkey,
+    freeze_authority: Option<&Pubkey>,
+    decimals: u8,
+  ) -> Keypair {
+    let mint_keypair = self.program_test.lock().await.keypairs.next_keypair();
+    self.create_mint(&mint_keypair, mint_authority, freeze_authority, decimals).await;
+
+    mint_keypair
+  }
+
   /// This is different from create_mint which follows the typical way of creating the a new mint account.
   /// Sometime we might not know the priv key of a mint account e.g. Wrapped Sol mint account but we want
   /// it to be part of the test execution context.
@@ -185,6 +284,57 @@ impl Spl {
     get_associated_token_address(wallet_address, spl_token_mint_address)
   }
 
+  /// Same as create_associated_account but it will not fail if the ATA already exists
+  pub async fn create_associated_account_idempotent(
+    &mut self,
+    wallet_address: &Pubkey,
+    spl_token_mint_address: &Pubkey,
+  ) -> Pubkey {
+    let mut lock_pt = self.program_test.lock().await;
+    let ix = create_associated_token_account_idempotent(
+      &lock_pt.context.payer.pubkey(),
+      wallet_address,
+      spl_token_mint_address,
+      &id(),
+    );
+
+    lock_pt.process_transaction(&[ix], None)
+      .await
+      .unwrap();
+
+    get_associated_token_address(wallet_address, spl_token_mint_address)
+  }
+
+  /// Returns the ATA of the given wallet creating it only if it does not already exist
+  pub async fn get_or_create_ata(
+    &mut self,
+    wallet_address: &Pubkey,
+    spl_token_mint_address: &Pubkey,
+  ) -> Pubkey {
+    if self.ata_exists(wallet_address, spl_token_mint_address).await {
+      return get_associated_token_address(wallet_address, spl_token_mint_address);
+    }
+
+    self.create_associated_account(wallet_address, spl_token_mint_address).await
+  }
+
+  pub async fn ata_exists(
+    &mut self,
+    wallet_address: &Pubkey,
+    spl_token_mint_address: &Pubkey,
+  ) -> bool {
+    let ata = get_associated_token_address(wallet_address, spl_token_mint_address);
+    let mut lock_pt = self.program_test.lock().await;
+
+    lock_pt
+      .context
+      .banks_client
+      .get_account(ata)
+      .await
+      .unwrap()
+      .is_some()
+  }
+
   pub fn get_associated_token_address(
     wallet_address: &Pubkey, 
     spl_token_mint_address: &Pubkey
@@ -235,8 +385,8 @@ impl Spl {
   ) {
     // 2. mint tokens to recipients
     for recipient in recipients {
-      // 1. create a new associated token account
-      self.create_associated_account(&recipient.pubkey(), &mint_account).await;
+      // 1. create a new associated token account if the recipient does not have one yet
+      self.create_associated_account_idempotent(&recipient.pubkey(), &mint_account).await;
 
       self.mint_tokens(
         mint_account,
@@ -247,36 +397,88 @@ impl Spl {
     }
   }
 
+  /// Creates the ATAs (if needed) and mints the given amount to each of the recipients. Unlike `airdrop`
+  /// the instructions for all recipients are packed into as few transactions as possible.
+  /// Returns the ATAs of the recipients in the same order.
+  pub async fn batch_airdrop(
+    &mut self,
+    mint_account: &Pubkey,
+    mint_authority: &Keypair,
+    recipients: &[(Pubkey, u64)],
+  ) -> Vec<Pubkey> {
+    let mut lock_pt = self.program_test.lock().await;
+    batch_mint(&mut lock_pt, mint_account, mint_authority, recipients).await
+  }
+
+  #[deprecated(note = "Use wrap_native_sol which always uses the native mint and returns the ATA")]
   pub async fn wrap_sol(
     &mut self,
     wrapped_sol_mint: &Pubkey,
     wallet: &Keypair,
     lamports: u64,
   ) {
-    // 1. Create a new ATA for the wrapped SOL Mint
-    let ata = self.create_associated_account(
-      &wallet.pubkey(),
-      &wrapped_sol_mint,
-    ).await;
+    self.wrap_sol_with_mint(wrapped_sol_mint, wallet, lamports).await;
+  }
 
-    // 2. Transfer SOL to the above ATA
-    {
-      let mut lock_pt = self.program_test.lock().await;
-      lock_pt.transfer_sol(&wallet, &ata, lamports).await;
-    }
+  /// Wraps the given lamports into the wallet's native mint ATA. The ATA is created if it does not exist yet
+  /// so this can be called multiple times for the same wallet.
+  pub async fn wrap_native_sol(
+    &mut self,
+    wallet: &Keypair,
+    lamports: u64,
+  ) -> Pubkey {
+    self.wrap_sol_with_mint(&native_mint::id(), wallet, lamports).await
+  }
 
-    // 3. Send sync native IX
-    {
-      let mut lock_pt = self.program_test.lock().await;
-      lock_pt.process_transaction(
-        &[
-          sync_native(&spl_token::id(), &ata).unwrap(),
-        ],
-        None,
-      )
+  async fn wrap_sol_with_mint(
+    &mut self,
+    wrapped_sol_mint: &Pubkey,
+    wallet: &Keypair,
+    lamports: u64,
+  ) -> Pubkey {
+    let ata = Self::get_associated_token_address(&wallet.pubkey(), wrapped_sol_mint);
+
+    let mut lock_pt = self.program_test.lock().await;
+    let instructions = [
+      // 1. Create the ATA for the wrapped SOL Mint if needed
+      create_associated_token_account_idempotent(
+        &lock_pt.payer.pubkey(),
+        &wallet.pubkey(),
+        wrapped_sol_mint,
+        &id(),
+      ),
+      // 2. Transfer SOL to the above ATA
+      system_instruction::transfer(&wallet.pubkey(), &ata, lamports),
+      // 3. Sync the token amount with the lamports held by the ATA
+      sync_native(&spl_token::id(), &ata).unwrap(),
+    ];
+
+    lock_pt.process_transaction(&instructions, Some(&[wallet]))
       .await
       .unwrap();
-    }
+
+    ata
+  }
+
+  /// Closes the wallet's wrapped SOL ATA sending all of its lamports back to the wallet
+  pub async fn unwrap_sol(&mut self, wallet: &Keypair) -> Result<(), ProgramError> {
+    let ata = Self::get_associated_token_address(&wallet.pubkey(), &native_mint::id());
+    let ix = close_account(
+      &spl_token::id(),
+      &ata,
+      &wallet.pubkey(),
+      &wallet.pubkey(),
+      &[&wallet.pubkey()],
+    )
+    .unwrap();
+
+    let mut lock_pt = self.program_test.lock().await;
+    lock_pt.process_transaction(&[ix], Some(&[wallet])).await
+  }
+
+  pub async fn get_wrapped_sol_balance(&mut self, wallet: &Pubkey) -> u64 {
+    let ata = Self::get_associated_token_address(wallet, &native_mint::id());
+    self.get_token_account(ata).await.amount
   }
 
   pub async fn set_mint_authority(
@@ -302,3 +504,45 @@ impl Spl {
       .unwrap();
   }
 }
+
+/// See `Spl::batch_airdrop`. Shared with TestAccountBuilder which holds the ProgramTest directly.
+pub(crate) async fn batch_mint(
+  pt: &mut ProgramTest,
+  mint_account: &Pubkey,
+  mint_authority: &Keypair,
+  recipients: &[(Pubkey, u64)],
+) -> Vec<Pubkey> {
+  let payer = pt.payer.pubkey();
+
+  let atas = recipients
+    .iter()
+    .map(|(recipient, _)| get_associated_token_address(recipient, mint_account))
+    .collect::<Vec<_>>();
+
+  let groups = recipients
+    .iter()
+    .zip(atas.iter())
+    .map(|((recipient, amount), ata)| vec![
+      create_associated_token_account_idempotent(&payer, recipient, mint_account, &id()),
+      spl_token::instruction::mint_to(
+        &spl_token::id(),
+        mint_account,
+        ata,
+        &mint_authority.pubkey(),
+        &[],
+        *amount,
+      )
+      .unwrap(),
+    ])
+    .collect();
+
+  let prefix = [ComputeBudgetInstruction::set_compute_unit_limit(BATCH_COMPUTE_UNIT_LIMIT)];
+
+  for instructions in pack_instructions(&payer, &prefix, groups) {
+    pt.process_transaction(&instructions, Some(&[mint_authority]))
+      .await
+      .unwrap();
+  }
+
+  atas
+}
@@ -1,40 +1,195 @@
-use futures::{stream, StreamExt};
-use std::sync::{Arc};
-use solana_program_test::{tokio::sync::{Mutex}};
+use std::{
+  collections::HashMap,
+  ops::Index,
+};
 use solana_sdk::{
+  pubkey::Pubkey,
   signature::{Keypair, Signer},
   native_token::sol_to_lamports,
 };
 use crate::{
   program_test::ProgramTest,
+  spl::batch_mint,
+  tools::clone_keypair,
 };
 
 #[derive(Default)]
 pub struct TestAccount {
   pub participants: Vec<Keypair>,
+  /// Index into participants of each named role
+  names: HashMap<String, usize>,
 }
 
 impl TestAccount {
   pub async fn new(pt: &mut ProgramTest, count: i32) -> Self {
-    let participants: Vec<Keypair> = (0..count)
-      .map(|_| Keypair::new())
-      .collect();
-      
+    let participants = pt.keypairs.next_keypairs(count.max(0) as usize);
+
     // fund the newly created accounts
-    let pt = Arc::new(Mutex::new(pt));
-    stream::iter(participants.iter())
-      .for_each(|account| {
-        let pt = Arc::clone(&pt);
-
-        async move {
-          let mut lock = pt.lock().await;
-          lock.airdrop(&account.pubkey(), sol_to_lamports(10_000_f64)).await
-        }
-      })
-      .await;
+    let recipients = participants
+      .iter()
+      .map(|account| (account.pubkey(), sol_to_lamports(10_000_f64)))
+      .collect::<Vec<_>>();
+    pt.batch_airdrop(&recipients).await;
 
     Self {
       participants,
+      names: HashMap::new(),
+    }
+  }
+
+  /// e.g. `TestAccount::builder().role("admin").role_with_lamports("alice", 1_000_000).build(&mut pt).await`
+  pub fn builder() -> TestAccountBuilder {
+    TestAccountBuilder::default()
+  }
+
+  pub fn get(&self, name: &str) -> Option<&Keypair> {
+    self.names.get(name).map(|index| &self.participants[*index])
+  }
+
+  pub fn pubkey(&self, name: &str) -> Pubkey {
+    self[name].pubkey()
+  }
+
+  /// Role names in the order they were declared
+  pub fn names(&self) -> Vec<&str> {
+    let mut names = self.names.iter().collect::<Vec<_>>();
+    names.sort_by_key(|(_, index)| **index);
+
+    names.into_iter().map(|(name, _)| name.as_str()).collect()
+  }
+}
+
+impl Index<&str> for TestAccount {
+  type Output = Keypair;
+
+  fn index(&self, name: &str) -> &Keypair {
+    self.get(name).unwrap_or_else(|| panic!("TEST-ACCOUNT-ERROR: unknown role {}", name))
+  }
+}
+
+struct Role {
+  name: String,
+  lamports: Option<u64>,
+  /// mint and amount in base units
+  tokens: Vec<(Pubkey, u64)>,
+}
+
+pub struct TestAccountBuilder {
+  default_lamports: u64,
+  roles: Vec<Role>,
+  mint_authorities: HashMap<Pubkey, Keypair>,
+  fund_directly: bool,
+}
+
+impl Default for TestAccountBuilder {
+  fn default() -> Self {
+    Self {
+      default_lamports: sol_to_lamports(10_000_f64),
+      roles: vec![],
+      mint_authorities: HashMap::new(),
+      fund_directly: false,
+    }
+  }
+}
+
+impl TestAccountBuilder {
+  /// Lamports airdropped to the roles that do not specify their own amount. Defaults to 10,000 SOL.
+  pub fn lamports(mut self, lamports: u64) -> Self {
+    self.default_lamports = lamports;
+    self
+  }
+
+  /// Writes the lamports of the roles directly into the bank instead of transferring them from the payer.
+  /// This is the fastest option for large numbers of accounts but the payer's balance and fees are not affected.
+  pub fn fund_directly(mut self) -> Self {
+    self.fund_directly = true;
+    self
+  }
+
+  pub fn role(self, name: &str) -> Self {
+    self.add_role(name, None)
+  }
+
+  pub fn role_with_lamports(self, name: &str, lamports: u64) -> Self {
+    self.add_role(name, Some(lamports))
+  }
+
+  pub fn roles(self, names: &[&str]) -> Self {
+    names.iter().fold(self, |builder, name| builder.role(name))
+  }
+
+  fn add_role(mut self, name: &str, lamports: Option<u64>) -> Self {
+    if self.roles.iter().any(|role| role.name == name) {
+      panic!("TEST-ACCOUNT-ERROR: duplicate role {}", name);
+    }
+
+    self.roles.push(Role {
+      name: name.to_string(),
+      lamports,
+      tokens: vec![],
+    });
+
+    self
+  }
+
+  /// Mints the given amount (in base units) of the mint to the ATA of an already declared role
+  pub fn token_balance(mut self, name: &str, mint: &Pubkey, mint_authority: &Keypair, amount: u64) -> Self {
+    let role = self.roles
+      .iter_mut()
+      .find(|role| role.name == name)
+      .unwrap_or_else(|| panic!("TEST-ACCOUNT-ERROR: unknown role {}", name));
+
+    role.tokens.push((*mint, amount));
+    self.mint_authorities
+      .entry(*mint)
+      .or_insert_with(|| clone_keypair(mint_authority));
+
+    self
+  }
+
+  /// Creates a keypair for each role and funds it with lamports and tokens
+  pub async fn build(self, pt: &mut ProgramTest) -> TestAccount {
+    let participants = pt.keypairs.next_keypairs(self.roles.len());
+
+    let recipients = self.roles
+      .iter()
+      .zip(participants.iter())
+      .map(|(role, account)| (account.pubkey(), role.lamports.unwrap_or(self.default_lamports)))
+      .filter(|(_, lamports)| *lamports > 0)
+      .collect::<Vec<_>>();
+
+    if self.fund_directly {
+      for (account, lamports) in recipients {
+        pt.set_lamports(&account, lamports);
+      }
+    } else {
+      pt.batch_airdrop(&recipients).await;
+    }
+
+    // mint the tokens of all roles in as few transactions as possible
+    let mut recipients_by_mint: Vec<(Pubkey, Vec<(Pubkey, u64)>)> = vec![];
+    for (role, account) in self.roles.iter().zip(participants.iter()) {
+      for (mint, amount) in &role.tokens {
+        match recipients_by_mint.iter_mut().find(|(m, _)| m == mint) {
+          Some((_, recipients)) => recipients.push((account.pubkey(), *amount)),
+          None => recipients_by_mint.push((*mint, vec![(account.pubkey(), *amount)])),
+        }
+      }
+    }
+
+    for (mint, recipients) in recipients_by_mint {
+      batch_mint(pt, &mint, &self.mint_authorities[&mint], &recipients).await;
+    }
+
+    let names = self.roles
+      .into_iter()
+      .enumerate()
+      .map(|(index, role)| (role.name, index))
+      .collect();
+
+    TestAccount {
+      participants,
+      names,
     }
   }
 }
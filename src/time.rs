@@ -1,6 +1,10 @@
-use std::borrow::Borrow;
+use std::{
+  borrow::Borrow,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use solana_sdk::{
-  clock::{Clock, UnixTimestamp},
+  clock::{Clock, UnixTimestamp, Epoch, Slot, DEFAULT_MS_PER_SLOT},
+  epoch_schedule::EpochSchedule,
   sysvar,
 };
 use solana_program_test::{
@@ -21,6 +25,64 @@ pub async fn get_clock(context: &mut ProgramTestContext) -> Clock {
     .unwrap_or_else(|| panic!("GET-TEST-ACCOUNT-ERROR: Account {}", clock_account))
 }
 
+/// Overrides the Clock sysvar. Note that the bank recalculates the clock when a new slot is reached
+/// e.g. after warp_to_slot, so this should be called after any slot warps.
+pub fn set_clock(context: &mut ProgramTestContext, clock: &Clock) {
+  context.set_sysvar(clock);
+}
+
+/// Sets the exact unix timestamp of the Clock sysvar leaving the rest of its fields untouched
+pub async fn set_unix_timestamp(context: &mut ProgramTestContext, unix_timestamp: UnixTimestamp) {
+  let mut clock = get_clock(context).await;
+  clock.unix_timestamp = unix_timestamp;
+
+  set_clock(context, &clock);
+}
+
+pub async fn get_epoch_schedule(context: &mut ProgramTestContext) -> EpochSchedule {
+  context.banks_client.get_sysvar::<EpochSchedule>().await.unwrap()
+}
+
+/// Overrides the EpochSchedule sysvar e.g. with `EpochSchedule::custom(32, 32, false)` to get short epochs.
+/// The epoch related fields of the Clock sysvar are recalculated from the overridden schedule every time the bank
+/// is warped through the helpers of this module.
+///
+/// Note! Only the sysvar view changes. The bank keeps using the genesis schedule, which solana-program-test does not
+/// allow to configure, so the bank's own epoch, stake activation and reward distribution do NOT follow the overridden
+/// schedule. Use it for programs that only read EpochSchedule and Clock. Tests of stake activation or rewards must
+/// stick to the genesis schedule, whose first epochs are already short since it warms up from 32 slot epochs.
+pub async fn set_epoch_schedule(context: &mut ProgramTestContext, epoch_schedule: &EpochSchedule) {
+  let clock = get_clock(context).await;
+  context.set_sysvar(epoch_schedule);
+  sync_clock_with_epoch_schedule(context, &clock).await;
+}
+
+/// Warps to the given slot keeping the Clock consistent with an overridden EpochSchedule sysvar
+pub async fn warp_to_slot(context: &mut ProgramTestContext, slot: Slot) {
+  let clock = get_clock(context).await;
+  context.warp_to_slot(slot).unwrap();
+  sync_clock_with_epoch_schedule(context, &clock).await;
+}
+
+async fn sync_clock_with_epoch_schedule(context: &mut ProgramTestContext, previous_clock: &Clock) {
+  let epoch_schedule = get_epoch_schedule(context).await;
+
+  if epoch_schedule == context.genesis_config().epoch_schedule {
+    return;
+  }
+
+  let mut clock = get_clock(context).await;
+  clock.epoch = epoch_schedule.get_epoch(clock.slot);
+  clock.leader_schedule_epoch = epoch_schedule.get_leader_schedule_epoch(clock.slot);
+  clock.epoch_start_timestamp = if clock.epoch == previous_clock.epoch {
+    previous_clock.epoch_start_timestamp
+  } else {
+    clock.unix_timestamp
+  };
+
+  set_clock(context, &clock);
+}
+
 #[allow(dead_code)]
 pub async fn advance_clock_past_timestamp(context: &mut ProgramTestContext, unix_timestamp: UnixTimestamp) {
   let mut clock = get_clock(context).await;
@@ -28,9 +90,7 @@ pub async fn advance_clock_past_timestamp(context: &mut ProgramTestContext, unix
 
   while clock.unix_timestamp <= unix_timestamp {
     // Since the exact time is not deterministic keep wrapping by arbitrary 400 slots until we pass the requested timestamp
-    context
-      .warp_to_slot(clock.slot + n * 400)
-      .unwrap();
+    warp_to_slot(context, clock.slot + n * 400).await;
 
     n += 1;
     clock = get_clock(context).await;
@@ -46,12 +106,64 @@ pub async fn advance_clock_by_min_timespan(context: &mut ProgramTestContext, tim
 #[allow(dead_code)]
 pub async fn advance_clock(context: &mut ProgramTestContext) {
   let clock = get_clock(context).await;
-  context.warp_to_slot(clock.slot + 2).unwrap();
+  warp_to_slot(context, clock.slot + 2).await;
 }
 
 #[allow(dead_code)]
 pub async fn advance_clock_by_slots(context: &mut ProgramTestContext, slots: u64) {
   let clock = get_clock(context).await;
-  context.warp_to_slot(clock.slot + slots).unwrap();
+  warp_to_slot(context, clock.slot + slots).await;
 }
 
+/// Moves the clock forward by exactly the given duration. The bank is warped forward by the number of slots
+/// that would be produced in that time and then the Clock sysvar's unix_timestamp is set to the exact value.
+/// Sub-second precision is ignored since the Clock only tracks seconds.
+pub async fn advance_time(context: &mut ProgramTestContext, duration: Duration) {
+  let clock = get_clock(context).await;
+  advance_to_timestamp(context, clock.unix_timestamp + duration.as_secs() as i64).await;
+}
+
+/// Moves the clock forward so that its unix_timestamp is exactly the given one
+pub async fn advance_to_timestamp(context: &mut ProgramTestContext, unix_timestamp: UnixTimestamp) {
+  let clock = get_clock(context).await;
+
+  if unix_timestamp < clock.unix_timestamp {
+    panic!("ADVANCE-TIME-ERROR: {} is before the current timestamp {}", unix_timestamp, clock.unix_timestamp);
+  }
+
+  let millis = (unix_timestamp - clock.unix_timestamp) as u64 * 1000;
+  let slots = (millis / DEFAULT_MS_PER_SLOT).max(1);
+
+  warp_to_slot(context, clock.slot + slots).await;
+  set_unix_timestamp(context, unix_timestamp).await;
+}
+
+pub async fn advance_to(context: &mut ProgramTestContext, time: SystemTime) {
+  let unix_timestamp = time
+    .duration_since(UNIX_EPOCH)
+    .expect("ADVANCE-TIME-ERROR: time is before the unix epoch")
+    .as_secs();
+
+  advance_to_timestamp(context, unix_timestamp as UnixTimestamp).await;
+}
+
+pub async fn first_slot_in_epoch(context: &mut ProgramTestContext, epoch: Epoch) -> Slot {
+  get_epoch_schedule(context).await.get_first_slot_in_epoch(epoch)
+}
+
+/// Warps to the first slot of the given epoch as defined by the EpochSchedule sysvar
+pub async fn advance_to_epoch(context: &mut ProgramTestContext, epoch: Epoch) {
+  let clock = get_clock(context).await;
+
+  if epoch <= clock.epoch {
+    panic!("ADVANCE-EPOCH-ERROR: epoch {} is not after the current epoch {}", epoch, clock.epoch);
+  }
+
+  let slot = first_slot_in_epoch(context, epoch).await;
+  warp_to_slot(context, slot).await;
+}
+
+pub async fn advance_epochs(context: &mut ProgramTestContext, epochs: u64) {
+  let clock = get_clock(context).await;
+  advance_to_epoch(context, clock.epoch + epochs).await;
+}
@@ -1,7 +1,10 @@
 use std::convert::TryFrom;
 use solana_sdk::{
+  pubkey::Pubkey,
   signature::{Keypair},
-  transaction::TransactionError,
+  instruction::Instruction,
+  packet::PACKET_DATA_SIZE,
+  transaction::{Transaction, TransactionError},
   transport::TransportError,
   instruction::InstructionError,
   program_error::ProgramError,
@@ -25,6 +28,50 @@ pub fn clone_keypair(source: &Keypair) -> Keypair {
   Keypair::from_bytes(&source.to_bytes()).unwrap()
 }
 
+/// Returns true if a transaction with the given instructions, once signed, fits into a single packet
+pub fn fits_in_transaction(instructions: &[Instruction], payer: &Pubkey) -> bool {
+  let transaction = Transaction::new_with_payer(instructions, Some(payer));
+
+  bincode::serialized_size(&transaction)
+    .map(|size| size as usize <= PACKET_DATA_SIZE)
+    .unwrap_or(false)
+}
+
+/// Greedily packs the given groups of instructions into as few transactions as possible. Instructions of the same
+/// group always end up in the same transaction and every transaction starts with the `prefix` instructions
+/// e.g. a compute budget instruction.
+pub fn pack_instructions(
+  payer: &Pubkey,
+  prefix: &[Instruction],
+  groups: Vec<Vec<Instruction>>,
+) -> Vec<Vec<Instruction>> {
+  let mut batches = vec![];
+  let mut current = prefix.to_vec();
+
+  for group in groups {
+    let mut candidate = current.clone();
+    candidate.extend_from_slice(&group);
+
+    if fits_in_transaction(&candidate, payer) {
+      current = candidate;
+      continue;
+    }
+
+    if current.len() > prefix.len() {
+      batches.push(current);
+    }
+
+    current = prefix.to_vec();
+    current.extend(group);
+  }
+
+  if current.len() > prefix.len() {
+    batches.push(current);
+  }
+
+  batches
+}
+
 pub fn map_transaction_error(transport_error: TransportError) -> ProgramError {
   match transport_error {
     TransportError::TransactionError(TransactionError::InstructionError(
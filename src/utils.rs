@@ -1,7 +1,111 @@
+use std::fmt;
+
+/// Returns 10^decimals i.e. the number of base units in a single token. Panics if it does not fit into u64
+/// which is the case for decimals > 19.
 pub fn unit(decimals: u8) -> u64 {
-  10_u32.pow(decimals as u32) as u64
+  10_u64
+    .checked_pow(decimals as u32)
+    .unwrap_or_else(|| panic!("UNIT-OVERFLOW: decimals {}", decimals))
 }
 
 pub fn to_base(val: u64, decimals: u8) -> u64 {
-  val * unit(decimals)
+  val
+    .checked_mul(unit(decimals))
+    .unwrap_or_else(|| panic!("TO-BASE-OVERFLOW: {} with decimals {}", val, decimals))
+}
+
+/// A token amount expressed in base units together with the decimals of the mint it belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenAmount {
+  pub amount: u64,
+  pub decimals: u8,
+}
+
+impl TokenAmount {
+  pub fn new(amount: u64, decimals: u8) -> Self {
+    Self {
+      amount,
+      decimals,
+    }
+  }
+
+  /// Creates an amount of whole tokens e.g. `from_tokens(5, 6)` is 5_000_000 base units
+  pub fn from_tokens(tokens: u64, decimals: u8) -> Option<Self> {
+    Self::parse(&tokens.to_string(), decimals)
+  }
+
+  /// Parses a decimal string such as "1.5" into base units. Returns None if the string is not a valid
+  /// non-negative number, has more fractional digits than the mint supports or overflows u64.
+  pub fn parse(ui_amount: &str, decimals: u8) -> Option<Self> {
+    let ui_amount = ui_amount.trim();
+    let (whole, fraction) = ui_amount.split_once('.').unwrap_or((ui_amount, ""));
+
+    if whole.is_empty() && fraction.is_empty() {
+      return None;
+    }
+
+    let fraction = fraction.trim_end_matches('0');
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if !is_digits(whole) || !is_digits(fraction) || fraction.len() > decimals as usize {
+      return None;
+    }
+
+    // right pad the fraction so that e.g. "1.5" with 6 decimals becomes "1500000". Working on the digits
+    // rather than multiplying by 10^decimals keeps any number of decimals from overflowing.
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    let digits = digits.trim_start_matches('0');
+    let amount = if digits.is_empty() { 0 } else { digits.parse::<u64>().ok()? };
+
+    Some(Self::new(amount, decimals))
+  }
+
+  /// Converts a float UI amount into base units. The value is rounded to the number of decimals of the mint.
+  pub fn from_f64(ui_amount: f64, decimals: u8) -> Option<Self> {
+    if !ui_amount.is_finite() || ui_amount < 0.0 {
+      return None;
+    }
+
+    Self::parse(&format!("{:.*}", decimals as usize, ui_amount), decimals)
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    self.amount as f64 / 10_f64.powi(self.decimals as i32)
+  }
+
+  /// Formats the amount as a decimal string without trailing zeros e.g. 1_500_000 with 6 decimals is "1.5"
+  pub fn to_ui_string(&self) -> String {
+    let decimals = self.decimals as usize;
+    // left pad the base units so that there is at least one digit before the decimal point
+    let digits = format!("{:0>width$}", self.amount, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+      return whole.to_string();
+    }
+
+    format!("{}.{}", whole, fraction)
+  }
+
+  pub fn checked_add(&self, other: TokenAmount) -> Option<Self> {
+    if self.decimals != other.decimals {
+      return None;
+    }
+
+    Some(Self::new(self.amount.checked_add(other.amount)?, self.decimals))
+  }
+
+  pub fn checked_sub(&self, other: TokenAmount) -> Option<Self> {
+    if self.decimals != other.decimals {
+      return None;
+    }
+
+    Some(Self::new(self.amount.checked_sub(other.amount)?, self.decimals))
+  }
+}
+
+impl fmt::Display for TokenAmount {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.to_ui_string())
+  }
 }
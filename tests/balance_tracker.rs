@@ -0,0 +1,94 @@
+mod common;
+
+use std::sync::Arc;
+use solana_program_test::tokio::{self, sync::Mutex};
+use solana_sdk::{
+  native_token::LAMPORTS_PER_SOL,
+  signature::{Keypair, Signer},
+  system_instruction,
+};
+use solana_test_utils::{balance_tracker::BalanceTracker, program_test::ProgramTest, spl::Spl, tools::clone_keypair};
+
+async fn start() -> (Arc<Mutex<ProgramTest>>, Keypair) {
+  let mut pt = ProgramTest::start_new(common::program_test_with_spl()).await;
+  let wallet = Keypair::new();
+  pt.airdrop(&wallet.pubkey(), 10 * LAMPORTS_PER_SOL).await;
+
+  (Arc::new(Mutex::new(pt)), wallet)
+}
+
+#[tokio::test]
+async fn payer_lamport_delta_excludes_fees() {
+  let (pt, wallet) = start().await;
+  let payer = pt.lock().await.payer.pubkey();
+  let lamports_per_signature = pt.lock().await.lamports_per_signature;
+
+  let mut tracker = BalanceTracker::new(pt.clone());
+  tracker.track_lamports(&payer).track_lamports(&wallet.pubkey());
+  tracker.snapshot().await;
+
+  let transfer_ix = system_instruction::transfer(&payer, &wallet.pubkey(), LAMPORTS_PER_SOL);
+  pt.lock().await.process_transaction(&[transfer_ix], None).await.unwrap();
+
+  let diff = tracker.diff().await;
+  assert_eq!(diff.fees, lamports_per_signature);
+  assert_eq!(diff.lamport_delta(&payer), -((LAMPORTS_PER_SOL + lamports_per_signature) as i128));
+  diff.assert_lamport_delta(&payer, -(LAMPORTS_PER_SOL as i128));
+  diff.assert_lamport_delta(&wallet.pubkey(), LAMPORTS_PER_SOL as i128);
+}
+
+#[tokio::test]
+async fn non_payer_lamport_delta_includes_its_own_fees() {
+  let (pt, wallet) = start().await;
+  let recipient = Keypair::new().pubkey();
+  let lamports_per_signature = pt.lock().await.lamports_per_signature;
+
+  let mut tracker = BalanceTracker::new(pt.clone());
+  tracker.track_lamports(&wallet.pubkey()).track_lamports(&recipient);
+  tracker.snapshot().await;
+
+  let transfer_ix = system_instruction::transfer(&wallet.pubkey(), &recipient, LAMPORTS_PER_SOL);
+  pt.lock().await.process_transaction_with_payer(&[transfer_ix], &wallet.pubkey(), &[&wallet]).await.unwrap();
+
+  let diff = tracker.diff().await;
+  // only the fees of the ProgramTest payer are tracked
+  assert_eq!(diff.fees, 0);
+  diff.assert_lamport_delta(&wallet.pubkey(), -((LAMPORTS_PER_SOL + lamports_per_signature) as i128));
+  diff.assert_lamport_delta(&recipient, LAMPORTS_PER_SOL as i128);
+}
+
+#[tokio::test]
+async fn token_deltas() {
+  let (pt, wallet) = start().await;
+  let mut spl = Spl::new(pt.clone());
+  let mint_authority = Keypair::new();
+  let mint = spl.create_new_mint(&mint_authority.pubkey(), None, 6).await.pubkey();
+  let recipient = Keypair::new();
+
+  let mut tracker = BalanceTracker::new(pt.clone());
+  tracker.track_tokens(&wallet.pubkey(), &mint).track_tokens(&recipient.pubkey(), &mint);
+  // neither of the token accounts exists yet
+  tracker.snapshot().await;
+
+  spl.airdrop(&mint, &mint_authority, &vec![clone_keypair(&wallet)], 1_000).await;
+  let diff = tracker.diff().await;
+  diff.assert_token_delta(&wallet.pubkey(), &mint, 1_000);
+  diff.assert_token_delta(&recipient.pubkey(), &mint, 0);
+
+  let wallet_ata = Spl::get_associated_token_address(&wallet.pubkey(), &mint);
+  let recipient_ata = spl.create_associated_account(&recipient.pubkey(), &mint).await;
+  let transfer_ix = spl_token::instruction::transfer(
+    &spl_token::id(),
+    &wallet_ata,
+    &recipient_ata,
+    &wallet.pubkey(),
+    &[],
+    400,
+  )
+  .unwrap();
+  pt.lock().await.process_transaction(&[transfer_ix], Some(&[&wallet])).await.unwrap();
+
+  let diff = tracker.diff().await;
+  diff.assert_token_delta(&wallet.pubkey(), &mint, -400);
+  assert_eq!(diff.token_delta(&recipient.pubkey(), &mint), 400);
+}
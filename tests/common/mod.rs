@@ -0,0 +1,16 @@
+use solana_program_test::processor;
+
+/// solana-program-test bundles SPL Token and the associated token account program as BPF builds, which can not be
+/// loaded in every environment, so the native builds are used instead
+#[allow(dead_code)]
+pub fn program_test_with_spl() -> solana_program_test::ProgramTest {
+  let mut program_test = solana_program_test::ProgramTest::default();
+  program_test.add_program("spl_token", spl_token::id(), processor!(spl_token::processor::Processor::process));
+  program_test.add_program(
+    "spl_associated_token_account",
+    spl_associated_token_account::id(),
+    processor!(spl_associated_token_account::processor::process_instruction),
+  );
+
+  program_test
+}
@@ -0,0 +1,199 @@
+//! The account data is produced by the code of spl-account-compression itself i.e. its header serialization,
+//! the ConcurrentMerkleTree operations and its canopy updates. The program is not run natively through
+//! ProgramTest because ConcurrentMerkleTree is not `repr(C)` in spl-concurrent-merkle-tree 0.1, so a host
+//! build may order its fields differently than the on-chain build. The tree is serialized in declaration order
+//! instead, which is the layout of the deployed program.
+
+use anchor_lang::AnchorDeserialize;
+use solana_sdk::pubkey::Pubkey;
+use spl_account_compression::{
+  canopy::{fill_in_proof_from_canopy, update_canopy},
+  events::ChangeLogEvent,
+  state::{ConcurrentMerkleTreeHeader, CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1},
+};
+use spl_concurrent_merkle_tree::concurrent_merkle_tree::ConcurrentMerkleTree;
+use solana_test_utils::merkle_tree::{
+  ConcurrentMerkleTreeMirror,
+  concurrent::{account_size, merkle_tree_size, ConcurrentMerkleTreeAccount, HEADER_SIZE, MirrorMismatch, Node},
+};
+
+const MAX_DEPTH: usize = 5;
+const MAX_BUFFER_SIZE: usize = 8;
+const CREATION_SLOT: u64 = 42;
+
+/// Replays the instructions of spl-account-compression against an in-memory account
+struct CompressionAccount {
+  id: Pubkey,
+  authority: Pubkey,
+  header: Vec<u8>,
+  tree: Box<ConcurrentMerkleTree<MAX_DEPTH, MAX_BUFFER_SIZE>>,
+  canopy: Vec<u8>,
+}
+
+impl CompressionAccount {
+  /// Same as the init_empty_merkle_tree instruction
+  fn init(canopy_depth: u32) -> Self {
+    let id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+
+    let mut header = ConcurrentMerkleTreeHeader::try_from_slice(&[0; CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1]).unwrap();
+    header.initialize(MAX_DEPTH as u32, MAX_BUFFER_SIZE as u32, &authority, CREATION_SLOT);
+
+    let mut tree = Box::new(ConcurrentMerkleTree::<MAX_DEPTH, MAX_BUFFER_SIZE>::new());
+    tree.initialize().unwrap();
+
+    let mut canopy = vec![0; account_size(MAX_DEPTH as u32, MAX_BUFFER_SIZE as u32, canopy_depth) - HEADER_SIZE
+      - merkle_tree_size(MAX_DEPTH as u32, MAX_BUFFER_SIZE as u32)];
+    update_canopy(&mut canopy, MAX_DEPTH as u32, None).unwrap();
+
+    Self {
+      id,
+      authority,
+      header: anchor_lang::AnchorSerialize::try_to_vec(&header).unwrap(),
+      tree,
+      canopy,
+    }
+  }
+
+  fn update_canopy(&mut self) {
+    let event = Box::<ChangeLogEvent>::from((self.tree.get_change_log(), self.id, self.tree.sequence_number));
+    update_canopy(&mut self.canopy, MAX_DEPTH as u32, Some(&event)).unwrap();
+  }
+
+  /// Same as the append instruction
+  fn append(&mut self, leaf: Node) {
+    self.tree.append(leaf).unwrap();
+    self.update_canopy();
+  }
+
+  /// Same as the replace_leaf instruction given the proof nodes passed as remaining accounts
+  fn replace_leaf(&mut self, root: Node, previous_leaf: Node, new_leaf: Node, proof: &[Node], index: u32) {
+    let mut proof = proof.to_vec();
+    fill_in_proof_from_canopy(&self.canopy, MAX_DEPTH as u32, index, &mut proof).unwrap();
+
+    self.tree.set_leaf(root, previous_leaf, new_leaf, &proof, index).unwrap();
+    self.update_canopy();
+  }
+
+  fn data(&self) -> Vec<u8> {
+    let tree = &self.tree;
+    let mut data = self.header.clone();
+
+    data.extend_from_slice(&tree.sequence_number.to_le_bytes());
+    data.extend_from_slice(&tree.active_index.to_le_bytes());
+    data.extend_from_slice(&tree.buffer_size.to_le_bytes());
+
+    for change_log in tree.change_logs.iter() {
+      data.extend_from_slice(&change_log.root);
+      change_log.path.iter().for_each(|node| data.extend_from_slice(node));
+      data.extend_from_slice(&change_log.index.to_le_bytes());
+      data.extend_from_slice(&change_log._padding.to_le_bytes());
+    }
+
+    let rightmost_proof = &tree.rightmost_proof;
+    rightmost_proof.proof.iter().for_each(|node| data.extend_from_slice(node));
+    data.extend_from_slice(&rightmost_proof.leaf);
+    data.extend_from_slice(&rightmost_proof.index.to_le_bytes());
+    data.extend_from_slice(&rightmost_proof._padding.to_le_bytes());
+
+    data.extend_from_slice(&self.canopy);
+
+    data
+  }
+}
+
+fn replace_leaf(
+  account: &mut CompressionAccount,
+  mirror: &mut ConcurrentMerkleTreeMirror,
+  index: u32,
+  new_leaf: Node,
+) {
+  account.replace_leaf(mirror.root(), mirror.leaf(index), new_leaf, &mirror.canopy_proof(index), index);
+  mirror.replace_leaf(index, new_leaf);
+}
+
+fn assert_mirror_matches(canopy_depth: u32) {
+  let mut account = CompressionAccount::init(canopy_depth);
+  let mut mirror = ConcurrentMerkleTreeMirror::new(MAX_DEPTH as u32, MAX_BUFFER_SIZE as u32, canopy_depth);
+
+  let data = account.data();
+  assert_eq!(data.len(), account_size(MAX_DEPTH as u32, MAX_BUFFER_SIZE as u32, canopy_depth));
+  assert_eq!(mirror.validate(&data), Ok(()));
+
+  // more operations than max_buffer_size so that the changelog buffer wraps around
+  for i in 1..=11_u8 {
+    account.append([i; 32]);
+    mirror.append([i; 32]);
+    assert_eq!(mirror.validate(&account.data()), Ok(()));
+  }
+
+  for (index, leaf) in [(0, [100; 32]), (7, [101; 32]), (10, [102; 32]), (3, [103; 32])] {
+    replace_leaf(&mut account, &mut mirror, index, leaf);
+    assert_eq!(mirror.validate(&account.data()), Ok(()));
+  }
+
+  // replacing the leaf at rightmost_index is an append
+  let rightmost_index = mirror.rightmost_index();
+  replace_leaf(&mut account, &mut mirror, rightmost_index, [104; 32]);
+
+  let data = account.data();
+  assert_eq!(mirror.validate(&data), Ok(()));
+
+  let unpacked = ConcurrentMerkleTreeAccount::unpack(&data).unwrap();
+  assert_eq!(unpacked.authority, account.authority);
+  assert_eq!(unpacked.creation_slot, CREATION_SLOT);
+  assert_eq!(unpacked.sequence_number, 16);
+  assert_eq!(unpacked.rightmost_index, 12);
+  assert_eq!(unpacked.rightmost_leaf, [104; 32]);
+  assert_eq!(unpacked.root, account.tree.get_root());
+
+  let mut diverged = mirror.clone();
+  diverged.replace_leaf(5, [200; 32]);
+  assert!(matches!(diverged.validate(&data), Err(MirrorMismatch::Root {..})));
+}
+
+#[test]
+fn mirror_matches_account_without_canopy() {
+  assert_mirror_matches(0);
+}
+
+#[test]
+fn mirror_matches_account_with_canopy() {
+  assert_mirror_matches(2);
+  assert_mirror_matches(MAX_DEPTH as u32);
+}
+
+#[test]
+fn mismatched_shape_is_reported() {
+  let data = CompressionAccount::init(2).data();
+
+  assert_eq!(
+    ConcurrentMerkleTreeMirror::new(MAX_DEPTH as u32, MAX_BUFFER_SIZE as u32, 3).validate(&data),
+    Err(MirrorMismatch::CanopyDepth {expected: 3, found_nodes: 6}),
+  );
+  assert_eq!(
+    ConcurrentMerkleTreeMirror::new(3, MAX_BUFFER_SIZE as u32, 2).validate(&data),
+    Err(MirrorMismatch::MaxDepth {expected: 3, found: MAX_DEPTH as u32}),
+  );
+}
+
+#[test]
+fn corrupted_account_is_rejected() {
+  let mut account = CompressionAccount::init(2);
+  account.append([1; 32]);
+  let data = account.data();
+  let mirror = ConcurrentMerkleTreeMirror::new(MAX_DEPTH as u32, MAX_BUFFER_SIZE as u32, 2);
+
+  // active index past the changelog buffer
+  let mut corrupted = data.clone();
+  corrupted[HEADER_SIZE + 8..HEADER_SIZE + 16].copy_from_slice(&(MAX_BUFFER_SIZE as u64).to_le_bytes());
+  assert!(ConcurrentMerkleTreeAccount::unpack(&corrupted).is_none());
+  assert_eq!(mirror.validate(&corrupted), Err(MirrorMismatch::InvalidAccountData));
+
+  let mut corrupted = data.clone();
+  corrupted[HEADER_SIZE + 8..HEADER_SIZE + 16].copy_from_slice(&u64::MAX.to_le_bytes());
+  assert!(ConcurrentMerkleTreeAccount::unpack(&corrupted).is_none());
+
+  assert!(ConcurrentMerkleTreeAccount::unpack(&data[..HEADER_SIZE + 10]).is_none());
+  assert!(ConcurrentMerkleTreeAccount::unpack(&[0; 100]).is_none());
+}
@@ -0,0 +1,50 @@
+use solana_program_test::{processor, tokio};
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_instruction};
+use solana_test_utils::program_test::{DuplicateTransactionStrategy, ProgramTest};
+
+async fn start(strategy: DuplicateTransactionStrategy) -> ProgramTest {
+  let mut program_test = solana_program_test::ProgramTest::default();
+  // the bundled BPF build of the memo program cannot be loaded in every environment, the native one always can
+  program_test.add_program("spl_memo", spl_memo::id(), processor!(spl_memo::processor::process_instruction));
+
+  let mut pt = ProgramTest::start_new(program_test).await;
+  pt.duplicate_transaction_strategy = strategy;
+  pt
+}
+
+async fn send_twice(pt: &mut ProgramTest) -> Pubkey {
+  let recipient = Pubkey::new_unique();
+  let transfer_ix = system_instruction::transfer(&pt.payer.pubkey(), &recipient, 1_000_000);
+
+  pt.process_transaction(std::slice::from_ref(&transfer_ix), None).await.unwrap();
+  pt.process_transaction(&[transfer_ix], None).await.unwrap();
+  recipient
+}
+
+#[tokio::test]
+async fn new_blockhash_processes_duplicate() {
+  let mut pt = start(DuplicateTransactionStrategy::NewBlockhash).await;
+  let fees_paid = pt.fees_paid;
+
+  let recipient = send_twice(&mut pt).await;
+  assert_eq!(pt.context.banks_client.get_balance(recipient).await.unwrap(), 2_000_000);
+  assert_eq!(pt.fees_paid, fees_paid + 2 * pt.lamports_per_signature);
+}
+
+#[tokio::test]
+async fn memo_nonce_processes_duplicate_in_same_slot() {
+  let mut pt = start(DuplicateTransactionStrategy::MemoNonce).await;
+  let fees_paid = pt.fees_paid;
+  let slot = pt.get_clock().await.slot;
+
+  let recipient = send_twice(&mut pt).await;
+  assert_eq!(pt.context.banks_client.get_balance(recipient).await.unwrap(), 2_000_000);
+  assert_eq!(pt.fees_paid, fees_paid + 2 * pt.lamports_per_signature);
+  assert_eq!(pt.get_clock().await.slot, slot);
+
+  // every further duplicate gets a memo of its own
+  let recipient = send_twice(&mut pt).await;
+  let transfer_ix = system_instruction::transfer(&pt.payer.pubkey(), &recipient, 1_000_000);
+  pt.process_transaction(&[transfer_ix], None).await.unwrap();
+  assert_eq!(pt.context.banks_client.get_balance(recipient).await.unwrap(), 3_000_000);
+}
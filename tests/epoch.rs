@@ -0,0 +1,36 @@
+use solana_program_test::tokio;
+use solana_sdk::epoch_schedule::{EpochSchedule, MINIMUM_SLOTS_PER_EPOCH};
+use solana_test_utils::program_test::ProgramTest;
+
+#[tokio::test]
+async fn genesis_schedule_warms_up() {
+  let mut pt = ProgramTest::start_new(solana_program_test::ProgramTest::default()).await;
+  let epoch_schedule = pt.get_epoch_schedule().await;
+
+  assert!(epoch_schedule.warmup);
+  assert_eq!(pt.first_slot_in_epoch(1).await, MINIMUM_SLOTS_PER_EPOCH);
+  assert_eq!(pt.first_slot_in_epoch(2).await, 3 * MINIMUM_SLOTS_PER_EPOCH);
+}
+
+#[tokio::test]
+async fn overridden_schedule_drives_the_clock() {
+  let epoch_schedule = EpochSchedule::custom(64, 64, false);
+  let mut pt = ProgramTest::start_with_epoch_schedule(
+    solana_program_test::ProgramTest::default(),
+    epoch_schedule,
+  ).await;
+  assert_eq!(pt.get_epoch_schedule().await, epoch_schedule);
+
+  pt.advance_to_epoch(2).await;
+  let clock = pt.get_clock().await;
+  assert_eq!(clock.slot, 128);
+  assert_eq!(clock.epoch, 2);
+  assert_eq!(clock.leader_schedule_epoch, epoch_schedule.get_leader_schedule_epoch(128));
+  assert_eq!(clock.epoch_start_timestamp, clock.unix_timestamp);
+
+  // warping within the epoch keeps its start timestamp
+  pt.advance_clock_by_slots(10).await;
+  let later = pt.get_clock().await;
+  assert_eq!(later.epoch, 2);
+  assert_eq!(later.epoch_start_timestamp, clock.epoch_start_timestamp);
+}
@@ -0,0 +1,86 @@
+use solana_program_test::{processor, tokio};
+use solana_sdk::{
+  account_info::AccountInfo,
+  entrypoint::ProgramResult,
+  instruction::{AccountMeta, Instruction},
+  msg,
+  program::invoke,
+  program_error::ProgramError,
+  pubkey::Pubkey,
+};
+use solana_test_utils::{logs::LogPrinting, program_test::ProgramTest};
+
+const OUTER_ID: Pubkey = Pubkey::new_from_array([1; 32]);
+const INNER_ID: Pubkey = Pubkey::new_from_array([2; 32]);
+
+/// Logs an amount, then fails if asked to or invokes the inner program
+fn process_outer(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+  msg!("outer amount: {}", 42);
+
+  if data.first() == Some(&1) {
+    return Err(ProgramError::Custom(3));
+  }
+
+  invoke(&Instruction::new_with_bytes(INNER_ID, &[], vec![]), accounts)?;
+  msg!("outer done");
+  Ok(())
+}
+
+fn process_inner(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+  msg!("inner");
+  Ok(())
+}
+
+async fn start() -> ProgramTest {
+  let mut program_test = solana_program_test::ProgramTest::default();
+  program_test.add_program("outer", OUTER_ID, processor!(process_outer));
+  program_test.add_program("inner", INNER_ID, processor!(process_inner));
+
+  ProgramTest::start_new(program_test).await
+}
+
+fn outer_ix(fail: bool) -> Instruction {
+  Instruction::new_with_bytes(OUTER_ID, &[fail as u8], vec![AccountMeta::new_readonly(INNER_ID, false)])
+}
+
+#[tokio::test]
+async fn logs_are_not_captured_by_default() {
+  let mut pt = start().await;
+  pt.log_printing = LogPrinting::OnFailure;
+
+  pt.process_transaction(&[outer_ix(false)], None).await.unwrap();
+  assert!(pt.process_transaction(&[outer_ix(true)], None).await.is_err());
+  assert!(pt.transaction_logs.is_empty());
+}
+
+#[tokio::test]
+async fn captured_logs() {
+  let mut pt = start().await;
+  pt.capture_logs = true;
+
+  pt.process_transaction(&[outer_ix(false)], None).await.unwrap();
+  pt.assert_log_contains("inner");
+  pt.assert_log_matches(r"amount: \d+");
+  pt.assert_program_log_contains(&INNER_ID, "inner");
+  assert_eq!(pt.last_logs().logs_of(&OUTER_ID), vec!["outer amount: 42", "outer done"]);
+  assert_eq!(pt.last_logs().logs_at_depth(2), vec!["inner"]);
+
+  assert!(pt.process_transaction(&[outer_ix(true)], None).await.is_err());
+  assert!(pt.last_logs().result.is_err());
+  assert_eq!(pt.last_logs().logs_of(&OUTER_ID), vec!["outer amount: 42"]);
+}
+
+#[tokio::test]
+async fn only_latest_logs_are_kept() {
+  let mut pt = start().await;
+  pt.capture_logs = true;
+  pt.max_transaction_logs = 2;
+
+  pt.process_transaction(&[outer_ix(true)], None).await.unwrap_err();
+  pt.process_transaction(&[outer_ix(false)], None).await.unwrap();
+  pt.process_transaction(&[outer_ix(false), outer_ix(false)], None).await.unwrap();
+
+  assert_eq!(pt.transaction_logs.len(), 2);
+  assert!(pt.transaction_logs[0].result.is_ok());
+  assert_eq!(pt.last_logs().logs_at_depth(2), vec!["inner", "inner"]);
+}
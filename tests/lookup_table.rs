@@ -0,0 +1,134 @@
+use solana_program_test::tokio;
+use solana_sdk::{
+  instruction::Instruction,
+  message::VersionedMessage,
+  pubkey::Pubkey,
+  signature::{Keypair, Signer},
+  slot_hashes::{SlotHashes, MAX_ENTRIES},
+  system_instruction, system_program,
+};
+use solana_address_lookup_table_program::instruction::{extend_lookup_table, close_lookup_table};
+use solana_test_utils::program_test::ProgramTest;
+
+async fn start() -> (ProgramTest, Keypair) {
+  let mut pt = ProgramTest::start_new(solana_program_test::ProgramTest::default()).await;
+  let authority = Keypair::new();
+  pt.airdrop(&authority.pubkey(), 1_000_000_000).await;
+
+  (pt, authority)
+}
+
+fn transfers(pt: &ProgramTest, recipients: &[Pubkey]) -> Vec<Instruction> {
+  recipients
+    .iter()
+    .map(|recipient| system_instruction::transfer(&pt.payer.pubkey(), recipient, 1_000_000))
+    .collect()
+}
+
+fn recipients(count: usize) -> Vec<Pubkey> {
+  (0..count).map(|_| Pubkey::new_unique()).collect()
+}
+
+#[tokio::test]
+async fn active_table_is_used() {
+  let (mut pt, authority) = start().await;
+  let recipients = recipients(40);
+  let table = pt.create_lookup_table_with_addresses(&authority, &[recipients.clone(), vec![system_program::id()]].concat()).await;
+
+  let instructions = transfers(&pt, &recipients);
+  pt.emulate_v0_transaction(&instructions, &[table], None).await.unwrap();
+
+  assert_eq!(pt.context.banks_client.get_balance(recipients[39]).await.unwrap(), 1_000_000);
+}
+
+#[tokio::test]
+async fn deactivating_table_is_still_used() {
+  let (mut pt, authority) = start().await;
+  let recipients = recipients(3);
+  let table = pt.create_lookup_table_with_addresses(&authority, &recipients).await;
+
+  pt.deactivate_lookup_table(&table.key, &authority).await;
+  pt.advance_clock_by_slots(10).await;
+
+  let instructions = transfers(&pt, &recipients);
+  pt.emulate_v0_transaction(&instructions, &[table], None).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(expected = "Attempted to lookup addresses from a table that does not exist")]
+async fn deactivated_table_is_rejected() {
+  let (mut pt, authority) = start().await;
+  let recipients = recipients(3);
+  let table = pt.create_lookup_table_with_addresses(&authority, &recipients).await;
+
+  pt.deactivate_lookup_table(&table.key, &authority).await;
+  pt.advance_clock_by_slots(600).await;
+
+  let instructions = transfers(&pt, &recipients);
+  pt.emulate_v0_transaction(&instructions, &[table], None).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(expected = "Address lookup contains an invalid index")]
+async fn addresses_extended_in_the_current_slot_are_rejected() {
+  let (mut pt, authority) = start().await;
+  let recipients = recipients(3);
+  let lookup_table = pt.create_lookup_table(&authority).await;
+
+  // extend without warping to the next slot like extend_lookup_table does
+  let extend_ix = extend_lookup_table(lookup_table, authority.pubkey(), Some(pt.payer.pubkey()), recipients.clone());
+  pt.process_transaction(&[extend_ix], Some(&[&authority])).await.unwrap();
+
+  let table = pt.get_lookup_table(&lookup_table).await;
+  let instructions = transfers(&pt, &recipients);
+  pt.emulate_v0_transaction(&instructions, &[table], None).await.unwrap();
+}
+
+#[tokio::test]
+async fn signers_and_programs_are_never_looked_up() {
+  let (mut pt, authority) = start().await;
+  let recipients = recipients(3);
+  let table_addresses = [vec![pt.payer.pubkey(), system_program::id()], recipients.clone()].concat();
+  let table = pt.create_lookup_table_with_addresses(&authority, &table_addresses).await;
+
+  let instructions = transfers(&pt, &recipients);
+  let transaction = pt.create_versioned_transaction(&instructions, std::slice::from_ref(&table), None).await;
+  let message = match &transaction.message {
+    VersionedMessage::V0(message) => message,
+    VersionedMessage::Legacy(_) => unreachable!(),
+  };
+
+  assert_eq!(message.account_keys, vec![pt.payer.pubkey(), system_program::id()]);
+  assert_eq!(message.address_table_lookups[0].writable_indexes, vec![2, 3, 4]);
+  assert!(message.address_table_lookups[0].readonly_indexes.is_empty());
+
+  pt.emulate_v0_transaction(&instructions, &[table], None).await.unwrap();
+}
+
+#[tokio::test]
+async fn table_is_closed_after_the_cool_down() {
+  let (mut pt, authority) = start().await;
+  let recipient = Pubkey::new_unique();
+  let table = pt.create_lookup_table_with_addresses(&authority, &recipients(3)).await;
+  let table_lamports = pt.context.banks_client.get_balance(table.key).await.unwrap();
+
+  pt.deactivate_lookup_table(&table.key, &authority).await;
+  let deactivation_slot = pt.get_clock().await.slot;
+
+  // still deactivating
+  let close_ix = close_lookup_table(table.key, authority.pubkey(), recipient);
+  assert!(pt.process_transaction(&[close_ix], Some(&[&authority])).await.is_err());
+
+  pt.close_lookup_table(&table.key, &authority, &recipient).await;
+
+  assert!(pt.context.banks_client.get_account(table.key).await.unwrap().is_none());
+  assert_eq!(pt.context.banks_client.get_balance(recipient).await.unwrap(), table_lamports);
+
+  // the cool down was waited out, SlotHashes is the bank's own history of the last slots
+  let current_slot = pt.get_clock().await.slot;
+  let slot_hashes = pt.get_sysvar::<SlotHashes>().await;
+  assert_eq!(slot_hashes.len(), MAX_ENTRIES);
+  assert_eq!(slot_hashes.first().unwrap().0, current_slot - 1);
+  assert!(slot_hashes.iter().all(|(slot, _)| *slot > deactivation_slot));
+  assert!(current_slot > deactivation_slot + MAX_ENTRIES as u64);
+}
@@ -0,0 +1,86 @@
+use solana_sdk::{keccak, pubkey::Pubkey};
+use solana_test_utils::merkle_tree::{
+  MerkleTree,
+  MerkleError,
+  Distribution,
+  leaf::{LeafFormat, claim_leaf, indexed_claim_leaf, double_hash},
+};
+
+fn claims() -> Vec<(Pubkey, u64)> {
+  (1..=7_u8)
+    .map(|i| (Pubkey::new_from_array([i; 32]), i as u64 * 1_000))
+    .collect()
+}
+
+#[test]
+fn leaf_bytes() {
+  let claimant = Pubkey::new_from_array([9; 32]);
+  let amount = 0x0102_0304_0506_0708_u64;
+
+  let mut claim_bytes = [9_u8; 32].to_vec();
+  claim_bytes.extend_from_slice(&[8, 7, 6, 5, 4, 3, 2, 1]);
+  let expected_claim = keccak::hash(&claim_bytes).0;
+  assert_eq!(claim_leaf(&claimant, amount), expected_claim);
+
+  let mut indexed_bytes = vec![3, 0, 0, 0, 0, 0, 0, 0];
+  indexed_bytes.extend_from_slice(&claim_bytes);
+  let expected_indexed = keccak::hash(&indexed_bytes).0;
+  assert_eq!(indexed_claim_leaf(3, &claimant, amount), expected_indexed);
+
+  assert_eq!(double_hash(expected_claim), keccak::hash(&expected_claim).0);
+
+  assert_eq!(LeafFormat::Claim.leaf(3, &claimant, amount, false), expected_claim);
+  assert_eq!(LeafFormat::Claim.leaf(3, &claimant, amount, true), double_hash(expected_claim));
+  assert_eq!(LeafFormat::IndexedClaim.leaf(3, &claimant, amount, false), expected_indexed);
+  assert_eq!(LeafFormat::IndexedClaim.leaf(3, &claimant, amount, true), double_hash(expected_indexed));
+}
+
+#[test]
+fn every_claim_verifies_against_the_root() {
+  for leaf_format in [LeafFormat::Claim, LeafFormat::IndexedClaim] {
+    for double_hashed in [false, true] {
+      let distribution = Distribution::new(&claims(), leaf_format, double_hashed);
+      let root = distribution.root().unwrap();
+
+      for (index, (claimant, amount)) in claims().into_iter().enumerate() {
+        let claim = distribution.claim(&claimant).unwrap();
+        assert_eq!(claim.index, index as u64);
+        assert_eq!(claim.amount, amount);
+
+        let leaf = distribution.leaf(&claimant).unwrap();
+        assert_eq!(leaf, leaf_format.leaf(index as u64, &claimant, amount, double_hashed));
+        assert!(MerkleTree::verify(distribution.proof(&claimant).unwrap(), root, leaf));
+      }
+
+      let claimants = [claims()[1].0, claims()[4].0, claims()[6].0];
+      let leaves = claimants.iter().map(|c| distribution.leaf(c).unwrap()).collect::<Vec<_>>();
+      assert!(distribution.multi_proof(&claimants).unwrap().verify(root, &leaves));
+    }
+  }
+}
+
+#[test]
+fn unknown_claimant_and_totals() {
+  let distribution = Distribution::new(&claims(), LeafFormat::Claim, false);
+  let unknown = Pubkey::new_unique();
+
+  assert_eq!(distribution.total_amount(), 28_000);
+  assert_eq!(distribution.leaves().len(), 7);
+  assert!(distribution.claim(&unknown).is_none());
+  assert!(distribution.proof(&unknown).is_none());
+  assert!(distribution.multi_proof(&[claims()[0].0, unknown]).is_none());
+}
+
+#[test]
+fn try_new_rejects_duplicate_leaves() {
+  let mut claims = claims();
+  claims.push(claims[2]);
+
+  assert_eq!(
+    Distribution::try_new(&claims, LeafFormat::Claim, false).err(),
+    Some(MerkleError::DuplicateLeaf {index: 7, duplicate_of: 2}),
+  );
+  // the index makes the leaves unique
+  assert!(Distribution::try_new(&claims, LeafFormat::IndexedClaim, false).is_ok());
+  assert_eq!(Distribution::try_new(&[], LeafFormat::Claim, false).err(), Some(MerkleError::EmptyTree));
+}
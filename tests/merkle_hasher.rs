@@ -0,0 +1,94 @@
+use solana_sdk::{keccak, hash};
+use solana_test_utils::merkle_tree::{
+  MerkleTree,
+  MerkleError,
+  MerkleHasher,
+  SolanaHasher,
+  Sha256SortedHasher,
+  KeccakPositionalHasher,
+  Sha256PositionalHasher,
+};
+
+fn leaves(count: u8) -> Vec<[u8; 32]> {
+  (0..count).map(|i| keccak::hash(&[i]).0).collect()
+}
+
+#[test]
+fn sorted_pair_hashers_ignore_the_order() {
+  let (low, high) = ([1; 32], [2; 32]);
+
+  assert_eq!(SolanaHasher::hash_pair(&low, &high), keccak::hashv(&[&low, &high]).0);
+  assert_eq!(SolanaHasher::hash_pair(&high, &low), keccak::hashv(&[&low, &high]).0);
+  assert_eq!(Sha256SortedHasher::hash_pair(&low, &high), hash::hashv(&[&low, &high]).to_bytes());
+  assert_eq!(Sha256SortedHasher::hash_pair(&high, &low), hash::hashv(&[&low, &high]).to_bytes());
+}
+
+#[test]
+fn positional_hashers_keep_the_order() {
+  let (low, high) = ([1; 32], [2; 32]);
+
+  assert_eq!(KeccakPositionalHasher::hash_pair(&high, &low), keccak::hashv(&[&high, &low]).0);
+  assert_eq!(Sha256PositionalHasher::hash_pair(&high, &low), hash::hashv(&[&high, &low]).to_bytes());
+  assert_ne!(KeccakPositionalHasher::hash_pair(&high, &low), KeccakPositionalHasher::hash_pair(&low, &high));
+}
+
+#[test]
+fn roots_depend_on_the_hasher() {
+  let (left, right) = ([2; 32], [1; 32]);
+  let leaves = vec![left, right];
+
+  assert_eq!(MerkleTree::new(leaves.clone()).root(), Some(keccak::hashv(&[&right, &left]).0));
+  assert_eq!(
+    MerkleTree::<Sha256SortedHasher>::from_leaves(leaves.clone()).root(),
+    Some(hash::hashv(&[&right, &left]).to_bytes()),
+  );
+  assert_eq!(
+    MerkleTree::<KeccakPositionalHasher>::from_leaves(leaves.clone()).root(),
+    Some(keccak::hashv(&[&left, &right]).0),
+  );
+  assert_eq!(
+    MerkleTree::<Sha256PositionalHasher>::from_leaves(leaves).root(),
+    Some(hash::hashv(&[&left, &right]).to_bytes()),
+  );
+}
+
+#[test]
+fn sha256_sorted_proofs_verify() {
+  let leaves = leaves(7);
+  let tree = MerkleTree::<Sha256SortedHasher>::from_leaves(leaves.clone());
+  let root = tree.root().unwrap();
+
+  let multi_proof = tree.multi_proof(&[0, 3, 6]);
+  assert!(multi_proof.verify_with::<Sha256SortedHasher>(root, &[leaves[0], leaves[3], leaves[6]]));
+  assert!(!multi_proof.verify(root, &[leaves[0], leaves[3], leaves[6]]));
+}
+
+fn assert_positional_proofs_verify<H: MerkleHasher>() {
+  for count in 1..=9 {
+    let leaves = leaves(count);
+    let tree = MerkleTree::<H>::from_leaves(leaves.clone());
+    let root = tree.root().unwrap();
+
+    for (index, leaf) in leaves.iter().enumerate() {
+      let proof = tree.positional_proof(index);
+      assert!(MerkleTree::<H>::verify_positional(&proof, root, *leaf), "{} leaves, index {}", count, index);
+
+      if let Some(node) = proof.first() {
+        let mut flipped = proof.clone();
+        flipped[0].is_left = !node.is_left;
+        assert!(!MerkleTree::<H>::verify_positional(&flipped, root, *leaf));
+      }
+    }
+
+    assert_eq!(
+      tree.try_positional_proof(count as usize),
+      Err(MerkleError::IndexOutOfRange {index: count as usize, leaves: count as usize}),
+    );
+  }
+}
+
+#[test]
+fn positional_proofs_verify() {
+  assert_positional_proofs_verify::<KeccakPositionalHasher>();
+  assert_positional_proofs_verify::<Sha256PositionalHasher>();
+}
@@ -0,0 +1,160 @@
+use solana_sdk::pubkey::Pubkey;
+use solana_test_utils::merkle_tree::{
+  MerkleTree,
+  Distribution,
+  Sha256PositionalHasher,
+  leaf::LeafFormat,
+  serialization::{SerializationError, to_hex},
+};
+
+fn claims() -> Vec<(Pubkey, u64)> {
+  (1..=7_u8)
+    .map(|i| (Pubkey::new_from_array([i; 32]), i as u64 * 1_000))
+    .collect()
+}
+
+fn assert_same_distribution(a: &Distribution, b: &Distribution) {
+  assert_eq!(a.root(), b.root());
+  assert_eq!(a.claims, b.claims);
+
+  for (claimant, _) in claims() {
+    assert_eq!(a.proof(&claimant), b.proof(&claimant));
+  }
+}
+
+#[test]
+fn merkle_tree_json_round_trip() {
+  let leaves = (0..5_u8).map(|i| [i; 32]).collect::<Vec<_>>();
+  let tree = MerkleTree::new(leaves.clone());
+
+  let json = tree.to_json_string();
+  let restored = MerkleTree::from_json_str(&json).unwrap();
+
+  assert_eq!(restored.root(), tree.root());
+  assert_eq!(restored.leaves(), leaves);
+  assert_eq!(restored.proof(&[1, 3]), tree.proof(&[1, 3]));
+  assert_eq!(restored.to_json_string(), json);
+}
+
+#[test]
+fn distribution_json_round_trip() {
+  let distribution = Distribution::new(&claims(), LeafFormat::IndexedClaim, true);
+
+  let json = distribution.to_json_string();
+  let restored = Distribution::from_json_str(&json).unwrap();
+
+  assert_same_distribution(&restored, &distribution);
+  assert_eq!(restored.to_json_string(), json);
+
+  // the proofs shipped in the json are the ones the tree produces
+  let exported = distribution.to_json();
+  for claim in &exported.claims {
+    let claimant = claim.claimant.parse::<Pubkey>().unwrap();
+    let proof = distribution.proof(&claimant).unwrap().iter().map(to_hex).collect::<Vec<_>>();
+
+    assert_eq!(claim.proof, proof);
+  }
+}
+
+#[test]
+fn distribution_json_round_trip_with_hasher() {
+  let distribution = Distribution::<Sha256PositionalHasher>::with_hasher(&claims(), LeafFormat::Claim, false);
+
+  let json = distribution.to_json();
+  let restored = Distribution::<Sha256PositionalHasher>::from_json_with_hasher(&json).unwrap();
+
+  assert_eq!(restored.root(), distribution.root());
+  assert_eq!(restored.to_json(), json);
+}
+
+#[test]
+fn distribution_json_with_wrong_root_is_rejected() {
+  let mut json = Distribution::new(&claims(), LeafFormat::Claim, false).to_json();
+  json.claims[0].amount += 1;
+
+  assert!(matches!(Distribution::from_json(&json), Err(SerializationError::RootMismatch)));
+}
+
+#[test]
+fn distribution_from_csv() {
+  let rows = claims()
+    .iter()
+    .map(|(claimant, amount)| format!("{},{}", claimant, amount))
+    .collect::<Vec<_>>()
+    .join("\n");
+  let expected = Distribution::new(&claims(), LeafFormat::Claim, true);
+
+  let without_header = Distribution::from_csv(&rows, LeafFormat::Claim, true).unwrap();
+  assert_same_distribution(&without_header, &expected);
+
+  let with_header = Distribution::from_csv(&format!("claimant,amount\n{}\n", rows), LeafFormat::Claim, true).unwrap();
+  assert_same_distribution(&with_header, &expected);
+
+  let restored = Distribution::from_json_str(&with_header.to_json_string()).unwrap();
+  assert_same_distribution(&restored, &expected);
+}
+
+#[test]
+fn distribution_from_invalid_csv() {
+  let claimant = Pubkey::new_from_array([1; 32]);
+
+  assert!(matches!(
+    Distribution::from_csv(&format!("{},abc", claimant), LeafFormat::Claim, false),
+    Err(SerializationError::Csv(1, _)),
+  ));
+  assert!(matches!(
+    Distribution::from_csv(&format!("{},1\nnot-a-pubkey,2", claimant), LeafFormat::Claim, false),
+    Err(SerializationError::Csv(2, _)),
+  ));
+}
+
+#[test]
+fn json_records_the_hasher() {
+  let distribution = Distribution::<Sha256PositionalHasher>::with_hasher(&claims(), LeafFormat::Claim, false);
+  let json = distribution.to_json();
+  assert_eq!(json.hasher, "sha256_positional");
+
+  assert!(matches!(
+    Distribution::from_json(&json),
+    Err(SerializationError::HasherMismatch {expected, found}) if expected == "keccak_sorted" && found == "sha256_positional",
+  ));
+
+  let tree = MerkleTree::<Sha256PositionalHasher>::from_leaves(vec![[1; 32], [2; 32]]);
+  assert!(matches!(MerkleTree::from_json(&tree.to_json()), Err(SerializationError::HasherMismatch {..})));
+}
+
+#[test]
+fn distribution_json_with_non_contiguous_indices_is_rejected() {
+  let mut json = Distribution::new(&claims(), LeafFormat::IndexedClaim, false).to_json();
+  json.claims.remove(3);
+
+  assert!(matches!(
+    Distribution::from_json(&json),
+    Err(SerializationError::NonContiguousIndex {expected: 3, found: 4}),
+  ));
+
+  let mut json = Distribution::new(&claims(), LeafFormat::IndexedClaim, false).to_json();
+  json.claims[6].index = 2;
+
+  assert!(matches!(
+    Distribution::from_json(&json),
+    Err(SerializationError::NonContiguousIndex {expected: 3, found: 2}),
+  ));
+}
+
+#[test]
+fn distribution_json_with_tampered_claims_is_rejected() {
+  let json = Distribution::new(&claims(), LeafFormat::Claim, false).to_json();
+
+  let mut tampered_leaf = json.clone();
+  tampered_leaf.claims[2].leaf = to_hex(&[7; 32]);
+  assert!(matches!(Distribution::from_json(&tampered_leaf), Err(SerializationError::LeafMismatch(2))));
+
+  let mut tampered_proof = json.clone();
+  tampered_proof.claims[5].proof[0] = to_hex(&[7; 32]);
+  assert!(matches!(Distribution::from_json(&tampered_proof), Err(SerializationError::ProofMismatch(5))));
+
+  let mut missing_proof_node = json;
+  missing_proof_node.claims[0].proof.pop();
+  assert!(matches!(Distribution::from_json(&missing_proof_node), Err(SerializationError::ProofMismatch(0))));
+}
@@ -0,0 +1,125 @@
+use solana_sdk::keccak;
+use solana_test_utils::merkle_tree::{MerkleTree, MerkleError, SolanaHasher};
+
+fn tree() -> MerkleTree {
+  MerkleTree::new((0..5_u8).map(|i| [i; 32]).collect())
+}
+
+#[test]
+fn empty_indices_have_an_empty_proof() {
+  let tree = tree();
+
+  assert!(tree.proof(&[]).is_empty());
+
+  let multi_proof = tree.multi_proof(&[]);
+  assert!(multi_proof.leaf_indices.is_empty());
+  assert!(multi_proof.proof.is_empty());
+  assert_eq!(multi_proof.total_leaves, 5);
+}
+
+#[test]
+fn try_proof_rejects_empty_indices() {
+  let tree = tree();
+
+  assert_eq!(tree.try_proof(&[]), Err(MerkleError::NoIndices));
+  assert_eq!(tree.try_multi_proof(&[]), Err(MerkleError::NoIndices));
+  assert_eq!(tree.try_proof(&[5]), Err(MerkleError::IndexOutOfRange {index: 5, leaves: 5}));
+  assert_eq!(tree.try_proof(&[1]).unwrap(), tree.proof(&[1]));
+}
+
+fn leaves(count: u8) -> Vec<[u8; 32]> {
+  (0..count).map(|i| keccak::hash(&[i]).0).collect()
+}
+
+/// Checks `verify_multi` against a multi proof produced by rs_merkle itself rather than by this crate
+fn assert_rs_merkle_proof_verifies(count: u8, indices: &[usize]) {
+  let leaves = leaves(count);
+  let tree = rs_merkle::MerkleTree::<SolanaHasher>::from_leaves(&leaves);
+  let root = tree.root().unwrap();
+  let proof = tree.proof(indices);
+  let leaves_with_indices = indices.iter().map(|i| (*i, leaves[*i])).collect::<Vec<_>>();
+  let proven_leaves = indices.iter().map(|i| leaves[*i]).collect::<Vec<_>>();
+
+  assert!(proof.verify(root, indices, &proven_leaves, leaves.len()));
+  assert!(MerkleTree::verify_multi(proof.proof_hashes(), root, &leaves_with_indices, leaves.len()));
+}
+
+#[test]
+fn verify_multi_accepts_rs_merkle_proofs() {
+  // adjacent leaves
+  assert_rs_merkle_proof_verifies(8, &[2, 3]);
+  // non adjacent leaves
+  assert_rs_merkle_proof_verifies(8, &[0, 5]);
+  assert_rs_merkle_proof_verifies(8, &[1, 4, 6]);
+  // all leaves
+  assert_rs_merkle_proof_verifies(8, &(0..8).collect::<Vec<_>>());
+  // single leaf
+  assert_rs_merkle_proof_verifies(8, &[5]);
+  assert_rs_merkle_proof_verifies(1, &[0]);
+  // odd leaf count, including the last leaf which is promoted without a sibling
+  assert_rs_merkle_proof_verifies(7, &[6]);
+  assert_rs_merkle_proof_verifies(7, &[1, 6]);
+  assert_rs_merkle_proof_verifies(5, &[0, 2, 4]);
+  assert_rs_merkle_proof_verifies(11, &(0..11).collect::<Vec<_>>());
+}
+
+#[test]
+fn verify_multi_rejects_tampered_proofs() {
+  let leaves = leaves(7);
+  let tree = rs_merkle::MerkleTree::<SolanaHasher>::from_leaves(&leaves);
+  let root = tree.root().unwrap();
+  let indices = [1, 4];
+  let leaves_with_indices = indices.iter().map(|i| (*i, leaves[*i])).collect::<Vec<_>>();
+  let proof = tree.proof(&indices).proof_hashes().to_vec();
+
+  assert!(MerkleTree::verify_multi(&proof, root, &leaves_with_indices, leaves.len()));
+
+  let mut tampered = proof.clone();
+  tampered[0][0] ^= 1;
+  assert!(!MerkleTree::verify_multi(&tampered, root, &leaves_with_indices, leaves.len()));
+
+  let mut extra = proof.clone();
+  extra.push([0; 32]);
+  assert!(!MerkleTree::verify_multi(&extra, root, &leaves_with_indices, leaves.len()));
+
+  assert!(!MerkleTree::verify_multi(&proof[1..], root, &leaves_with_indices, leaves.len()));
+
+  let wrong_leaf = [(1, leaves[1]), (4, leaves[5])];
+  assert!(!MerkleTree::verify_multi(&proof, root, &wrong_leaf, leaves.len()));
+}
+
+#[test]
+fn root_history_and_rollback() {
+  let mut tree = tree();
+  let original_root = tree.root().unwrap();
+  assert!(tree.root_history().is_empty());
+  assert!(!tree.rollback());
+
+  tree.append(vec![[5; 32], [6; 32]]);
+  let appended_root = tree.root().unwrap();
+  assert_eq!(tree.leaves().len(), 7);
+  assert_eq!(appended_root, MerkleTree::new((0..7_u8).map(|i| [i; 32]).collect()).root().unwrap());
+
+  tree.update_leaf(0, [9; 32]);
+  let updated_root = tree.root().unwrap();
+  assert_eq!(tree.leaves()[0], [9; 32]);
+  assert_eq!(tree.root_history(), vec![original_root, appended_root]);
+  assert!(tree.is_known_root(&original_root));
+  assert!(tree.is_known_root(&updated_root));
+  assert!(!tree.is_known_root(&[0; 32]));
+
+  // a failed update does not add to the history
+  assert_eq!(tree.try_update_leaf(7, [9; 32]).err(), Some(MerkleError::IndexOutOfRange {index: 7, leaves: 7}));
+  assert_eq!(tree.root_history().len(), 2);
+
+  assert!(tree.rollback());
+  assert_eq!(tree.root(), Some(appended_root));
+  assert_eq!(tree.leaves()[0], [0; 32]);
+
+  assert!(tree.rollback());
+  assert_eq!(tree.root(), Some(original_root));
+  assert_eq!(tree.leaves().len(), 5);
+  assert!(tree.root_history().is_empty());
+  assert!(!tree.rollback());
+  assert!(!tree.is_known_root(&appended_root));
+}
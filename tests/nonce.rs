@@ -0,0 +1,133 @@
+use solana_program_test::tokio;
+use solana_sdk::{
+  instruction::InstructionError,
+  pubkey::Pubkey,
+  signature::{Keypair, Signer},
+  system_instruction,
+  transaction::TransactionError,
+};
+use solana_test_utils::{program_test::ProgramTest, tools::clone_keypair};
+
+async fn start() -> (ProgramTest, Keypair, Pubkey) {
+  let mut pt = ProgramTest::start_new(solana_program_test::ProgramTest::default()).await;
+  let authority = Keypair::new();
+  let nonce_account = pt.create_nonce_account(&authority.pubkey()).await.pubkey();
+
+  // a nonce can only be used once the blockhash it was stored with has changed
+  pt.advance_clock_by_slots(1).await;
+
+  (pt, authority, nonce_account)
+}
+
+async fn balance(pt: &mut ProgramTest, address: &Pubkey) -> u64 {
+  pt.context.banks_client.get_balance(*address).await.unwrap()
+}
+
+#[tokio::test]
+async fn nonce_transaction_is_processed_once() {
+  let (mut pt, authority, nonce_account) = start().await;
+  let payer = clone_keypair(&pt.payer);
+  let recipient = Pubkey::new_unique();
+  let transfer_ix = system_instruction::transfer(&payer.pubkey(), &recipient, 1_000_000);
+
+  let nonce = pt.get_nonce(&nonce_account).await;
+  let transaction = pt.create_nonce_transaction(
+    &[transfer_ix],
+    &nonce_account,
+    &authority.pubkey(),
+    &payer.pubkey(),
+    &[&payer, &authority],
+  ).await;
+
+  // signed offline and sent later
+  pt.advance_clock_by_slots(5).await;
+  let fees_paid = pt.fees_paid;
+
+  pt.process_nonce_transaction(&transaction, &[&payer, &authority]).await.unwrap();
+  assert_eq!(balance(&mut pt, &recipient).await, 1_000_000);
+  assert_eq!(pt.fees_paid, fees_paid + 2 * pt.lamports_per_signature);
+  assert_ne!(pt.get_nonce(&nonce_account).await, nonce);
+
+  pt.advance_clock_by_slots(1).await;
+  assert_eq!(
+    pt.process_nonce_transaction(&transaction, &[&payer, &authority]).await,
+    Err(TransactionError::BlockhashNotFound),
+  );
+  assert_eq!(balance(&mut pt, &recipient).await, 1_000_000);
+}
+
+#[tokio::test]
+async fn failed_instruction_advances_nonce_and_charges_fee() {
+  let (mut pt, authority, nonce_account) = start().await;
+  let payer_key = pt.payer.pubkey();
+  let poor = Keypair::new();
+  pt.airdrop(&poor.pubkey(), 10_000_000_000).await;
+  let recipient = Pubkey::new_unique();
+  let overdraft_ix = system_instruction::transfer(&poor.pubkey(), &recipient, 20_000_000_000);
+
+  let nonce = pt.get_nonce(&nonce_account).await;
+  let payer_balance = balance(&mut pt, &payer_key).await;
+  let fees_paid = pt.fees_paid;
+
+  let result = pt.process_transaction_with_nonce(&[overdraft_ix], &nonce_account, &authority, Some(&[&poor])).await;
+  assert_eq!(result, Err(TransactionError::InstructionError(1, InstructionError::Custom(1))));
+
+  // payer, nonce authority and poor signed the transaction
+  let fee = 3 * pt.lamports_per_signature;
+  assert_eq!(pt.fees_paid, fees_paid + fee);
+  assert_eq!(balance(&mut pt, &payer_key).await, payer_balance - fee);
+  assert_eq!(balance(&mut pt, &poor.pubkey()).await, 10_000_000_000);
+  assert_eq!(balance(&mut pt, &recipient).await, 0);
+
+  let advanced_nonce = pt.get_nonce(&nonce_account).await;
+  assert_ne!(advanced_nonce, nonce);
+
+  // the advanced nonce can be used once the blockhash changes
+  pt.advance_clock_by_slots(1).await;
+  let transfer_ix = system_instruction::transfer(&poor.pubkey(), &recipient, 1_000_000_000);
+  pt.process_transaction_with_nonce(&[transfer_ix], &nonce_account, &authority, Some(&[&poor])).await.unwrap();
+  assert_eq!(balance(&mut pt, &recipient).await, 1_000_000_000);
+  assert_ne!(pt.get_nonce(&nonce_account).await, advanced_nonce);
+}
+
+#[tokio::test]
+async fn rejected_nonce_transaction_changes_nothing() {
+  let (mut pt, authority, nonce_account) = start().await;
+  let payer_key = pt.payer.pubkey();
+  let recipient = Pubkey::new_unique();
+  let transfer_ix = system_instruction::transfer(&payer_key, &recipient, 1_000_000);
+
+  let nonce = pt.get_nonce(&nonce_account).await;
+  let payer_balance = balance(&mut pt, &payer_key).await;
+  let fees_paid = pt.fees_paid;
+
+  // the transaction is not signed by the nonce authority
+  let result = pt.process_transaction_with_nonce(&[transfer_ix], &nonce_account, &Keypair::new(), None).await;
+  assert_eq!(result, Err(TransactionError::BlockhashNotFound));
+
+  assert_eq!(pt.get_nonce(&nonce_account).await, nonce);
+  assert_eq!(pt.fees_paid, fees_paid);
+  assert_eq!(balance(&mut pt, &payer_key).await, payer_balance);
+
+  pt.advance_clock_by_slots(1).await;
+  assert!(pt.advance_nonce(&nonce_account, &authority).await.is_ok());
+  assert_ne!(pt.get_nonce(&nonce_account).await, nonce);
+}
+
+#[tokio::test]
+#[should_panic(expected = "must be passed to sign it again")]
+async fn every_signer_must_be_passed() {
+  let (mut pt, authority, nonce_account) = start().await;
+  let payer = clone_keypair(&pt.payer);
+  let transfer_ix = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1_000_000);
+
+  let transaction = pt.create_nonce_transaction(
+    &[transfer_ix],
+    &nonce_account,
+    &authority.pubkey(),
+    &payer.pubkey(),
+    &[&payer, &authority],
+  ).await;
+
+  pt.process_nonce_transaction(&transaction, &[&payer]).await.unwrap();
+}
@@ -0,0 +1,79 @@
+use solana_program_test::tokio;
+use solana_sdk::{
+  pubkey::Pubkey,
+  signature::{Keypair, Signer},
+  system_instruction,
+};
+use solana_test_utils::{program_test::ProgramTest, tools::clone_keypair};
+
+const USER_LAMPORTS: u64 = 1_000_000_000;
+
+async fn start() -> (ProgramTest, Keypair) {
+  let mut pt = ProgramTest::start_new(solana_program_test::ProgramTest::default()).await;
+  let user = Keypair::new();
+  pt.airdrop(&user.pubkey(), USER_LAMPORTS).await;
+
+  (pt, user)
+}
+
+async fn balance(pt: &mut ProgramTest, address: &Pubkey) -> u64 {
+  pt.context.banks_client.get_balance(*address).await.unwrap()
+}
+
+#[tokio::test]
+async fn user_pays_the_fees() {
+  let (mut pt, user) = start().await;
+  let payer = pt.payer.pubkey();
+  let payer_balance = balance(&mut pt, &payer).await;
+  let fees_paid = pt.fees_paid;
+  let recipient = Pubkey::new_unique();
+
+  let transfer = system_instruction::transfer(&user.pubkey(), &recipient, 1_000_000);
+  pt.process_transaction_with_payer(&[transfer], &user.pubkey(), &[&user]).await.unwrap();
+
+  assert_eq!(balance(&mut pt, &user.pubkey()).await, USER_LAMPORTS - 1_000_000 - pt.lamports_per_signature);
+  assert_eq!(balance(&mut pt, &recipient).await, 1_000_000);
+  assert_eq!(balance(&mut pt, &payer).await, payer_balance);
+  assert_eq!(pt.fees_paid, fees_paid);
+}
+
+#[tokio::test]
+async fn duplicate_and_unrequired_signers_are_ignored() {
+  let (mut pt, user) = start().await;
+  let payer = clone_keypair(&pt.payer);
+  let unrelated = Keypair::new();
+  let recipient = Pubkey::new_unique();
+
+  // the payer is not required by the instruction nor is it the fee payer, so it must not sign
+  let transfer = system_instruction::transfer(&user.pubkey(), &recipient, 1_000_000);
+  pt.process_transaction_with_payer(&[transfer], &user.pubkey(), &[&user, &payer, &user, &unrelated])
+    .await
+    .unwrap();
+
+  // only the required signature is paid for
+  assert_eq!(balance(&mut pt, &user.pubkey()).await, USER_LAMPORTS - 1_000_000 - pt.lamports_per_signature);
+}
+
+#[tokio::test]
+async fn payer_and_additional_signers() {
+  let (mut pt, user) = start().await;
+  let payer = pt.payer.pubkey();
+  let fees_paid = pt.fees_paid;
+  let recipient = Pubkey::new_unique();
+
+  let transfer = system_instruction::transfer(&user.pubkey(), &recipient, 1_000_000);
+  pt.process_transaction(&[transfer], Some(&[&user, &user])).await.unwrap();
+
+  assert_eq!(balance(&mut pt, &user.pubkey()).await, USER_LAMPORTS - 1_000_000);
+  assert_eq!(pt.fees_paid, fees_paid + 2 * pt.lamports_per_signature);
+  assert_ne!(balance(&mut pt, &payer).await, 0);
+}
+
+#[tokio::test]
+#[should_panic(expected = "PROCESS-TRANSACTION-ERROR: missing signer")]
+async fn missing_signer_panics() {
+  let (mut pt, user) = start().await;
+
+  let transfer = system_instruction::transfer(&user.pubkey(), &Pubkey::new_unique(), 1_000_000);
+  pt.process_transaction(&[transfer], None).await.unwrap();
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use std::sync::Arc;
+use solana_program_test::tokio::{self, sync::Mutex};
+use solana_sdk::{signature::{Keypair, Signer}, pubkey::Pubkey};
+use spl_token::native_mint;
+use solana_test_utils::{program_test::ProgramTest, spl::Spl};
+
+const WALLET_LAMPORTS: u64 = 10_000_000_000;
+
+async fn start() -> (Arc<Mutex<ProgramTest>>, Spl, Keypair) {
+  let mut pt = ProgramTest::start_new(common::program_test_with_spl()).await;
+  let wallet = Keypair::new();
+  pt.airdrop(&wallet.pubkey(), WALLET_LAMPORTS).await;
+
+  let pt = Arc::new(Mutex::new(pt));
+  let spl = Spl::new(pt.clone());
+
+  (pt, spl, wallet)
+}
+
+async fn balance(pt: &Arc<Mutex<ProgramTest>>, address: &Pubkey) -> u64 {
+  pt.lock().await.context.banks_client.get_balance(*address).await.unwrap()
+}
+
+#[tokio::test]
+async fn wrap_native_sol_is_idempotent() {
+  let (pt, mut spl, wallet) = start().await;
+
+  let ata = spl.wrap_native_sol(&wallet, 1_000_000).await;
+  assert_eq!(ata, Spl::get_associated_token_address(&wallet.pubkey(), &native_mint::id()));
+  assert_eq!(spl.get_wrapped_sol_balance(&wallet.pubkey()).await, 1_000_000);
+
+  assert_eq!(spl.wrap_native_sol(&wallet, 2_000_000).await, ata);
+  assert_eq!(spl.get_wrapped_sol_balance(&wallet.pubkey()).await, 3_000_000);
+  // the payer pays the fees and the rent of the ATA
+  assert_eq!(balance(&pt, &wallet.pubkey()).await, WALLET_LAMPORTS - 3_000_000);
+}
+
+#[tokio::test]
+#[allow(deprecated)]
+async fn wrap_sol_with_explicit_mint() {
+  let (_pt, mut spl, wallet) = start().await;
+
+  spl.wrap_sol(&native_mint::id(), &wallet, 1_000_000).await;
+  spl.wrap_sol(&native_mint::id(), &wallet, 1_000_000).await;
+  assert_eq!(spl.get_wrapped_sol_balance(&wallet.pubkey()).await, 2_000_000);
+}
+
+#[tokio::test]
+async fn unwrap_sol_returns_all_lamports() {
+  let (pt, mut spl, wallet) = start().await;
+
+  let ata = spl.wrap_native_sol(&wallet, 1_000_000).await;
+  let ata_lamports = balance(&pt, &ata).await;
+
+  spl.unwrap_sol(&wallet).await.unwrap();
+  assert_eq!(balance(&pt, &ata).await, 0);
+  // the wrapped lamports come back along with the rent the payer put into the ATA
+  assert_eq!(balance(&pt, &wallet.pubkey()).await, WALLET_LAMPORTS - 1_000_000 + ata_lamports);
+
+  assert!(spl.unwrap_sol(&wallet).await.is_err());
+}
@@ -0,0 +1,94 @@
+use solana_program_test::{processor, tokio};
+use solana_sdk::{
+  account_info::AccountInfo,
+  clock::Clock,
+  entrypoint::ProgramResult,
+  epoch_schedule::EpochSchedule,
+  instruction::{AccountMeta, Instruction},
+  program_error::ProgramError,
+  pubkey::Pubkey,
+  rent::Rent,
+  signature::Signer,
+  slot_hashes::SlotHashes,
+  sysvar::Sysvar,
+};
+use solana_test_utils::program_test::ProgramTest;
+
+const RENT_CHECK_PROGRAM_ID: Pubkey = Pubkey::new_from_array([8; 32]);
+
+/// Fails unless the given account is rent exempt according to the Rent sysvar
+fn process_rent_check(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+  let account = &accounts[0];
+
+  match Rent::get()?.is_exempt(account.lamports(), account.data_len()) {
+    true => Ok(()),
+    false => Err(ProgramError::Custom(2)),
+  }
+}
+
+async fn start() -> ProgramTest {
+  let mut program_test = solana_program_test::ProgramTest::default();
+  program_test.add_program("rent_check", RENT_CHECK_PROGRAM_ID, processor!(process_rent_check));
+
+  ProgramTest::start_new(program_test).await
+}
+
+fn rent_check_ix(account: &Pubkey) -> Instruction {
+  Instruction::new_with_bytes(RENT_CHECK_PROGRAM_ID, &[], vec![AccountMeta::new_readonly(*account, false)])
+}
+
+fn doubled_rent() -> Rent {
+  Rent {
+    lamports_per_byte_year: Rent::default().lamports_per_byte_year * 2,
+    ..Rent::default()
+  }
+}
+
+#[tokio::test]
+async fn get_sysvar_reads_the_bank() {
+  let mut pt = start().await;
+
+  assert_eq!(pt.get_sysvar::<Clock>().await, pt.get_clock().await);
+  assert_eq!(pt.get_sysvar::<EpochSchedule>().await, pt.get_epoch_schedule().await);
+
+  let slot = pt.get_clock().await.slot;
+  assert_eq!(pt.get_sysvar::<SlotHashes>().await.first().map(|(slot, _)| *slot + 1), Some(slot));
+}
+
+#[tokio::test]
+async fn set_sysvar_is_seen_by_programs() {
+  let mut pt = start().await;
+  let account = pt.create_rent_exempt_account(100, &Pubkey::new_unique()).await;
+  pt.process_transaction(&[rent_check_ix(&account.pubkey())], None).await.unwrap();
+
+  pt.set_sysvar(&doubled_rent());
+  assert_eq!(pt.get_sysvar::<Rent>().await, doubled_rent());
+  assert_eq!(
+    pt.process_transaction(&[rent_check_ix(&account.pubkey())], None).await,
+    Err(ProgramError::Custom(2)),
+  );
+}
+
+#[tokio::test]
+async fn set_sysvar_keeps_cached_rent_in_sync() {
+  let mut pt = start().await;
+
+  pt.set_sysvar(&doubled_rent());
+  assert_eq!(pt.rent, doubled_rent());
+  assert_eq!(pt.rent_exempt_balance(100), doubled_rent().minimum_balance(100));
+
+  // account creating helpers fund the new accounts with the overridden rent
+  let account = pt.create_rent_exempt_account(100, &Pubkey::new_unique()).await;
+  pt.process_transaction(&[rent_check_ix(&account.pubkey())], None).await.unwrap();
+}
+
+#[tokio::test]
+async fn refresh_rent_after_direct_override() {
+  let mut pt = start().await;
+
+  pt.context.set_sysvar(&doubled_rent());
+  assert_eq!(pt.rent, Rent::default());
+
+  assert_eq!(pt.refresh_rent().await, doubled_rent());
+  assert_eq!(pt.rent, doubled_rent());
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use std::sync::Arc;
+use solana_program_test::tokio::{self, sync::Mutex};
+use solana_sdk::{signature::{Keypair, Signer}, pubkey::Pubkey};
+use solana_test_utils::{program_test::ProgramTest, spl::Spl, test_account::TestAccount};
+
+async fn balance(pt: &mut ProgramTest, address: &Pubkey) -> u64 {
+  pt.context.banks_client.get_balance(*address).await.unwrap()
+}
+
+#[tokio::test]
+async fn non_positive_count_creates_no_participants() {
+  let mut pt = ProgramTest::start_new(solana_program_test::ProgramTest::default()).await;
+
+  assert!(TestAccount::new(&mut pt, -1).await.participants.is_empty());
+  assert!(TestAccount::new(&mut pt, 0).await.participants.is_empty());
+  assert_eq!(TestAccount::new(&mut pt, 2).await.participants.len(), 2);
+}
+
+#[tokio::test]
+async fn builder_funds_roles() {
+  let mut pt = ProgramTest::start_new(solana_program_test::ProgramTest::default()).await;
+
+  let accounts = TestAccount::builder()
+    .lamports(5_000_000)
+    .role("admin")
+    .role_with_lamports("alice", 7_000_000)
+    .roles(&["bob", "carol"])
+    .build(&mut pt)
+    .await;
+
+  assert_eq!(accounts.names(), vec!["admin", "alice", "bob", "carol"]);
+  assert_eq!(accounts.participants.len(), 4);
+  assert_eq!(balance(&mut pt, &accounts.pubkey("admin")).await, 5_000_000);
+  assert_eq!(balance(&mut pt, &accounts["alice"].pubkey()).await, 7_000_000);
+  assert_eq!(balance(&mut pt, &accounts.pubkey("carol")).await, 5_000_000);
+  assert!(accounts.get("dave").is_none());
+}
+
+#[tokio::test]
+async fn fund_directly_does_not_touch_the_payer() {
+  let mut pt = ProgramTest::start_new(solana_program_test::ProgramTest::default()).await;
+  let payer = pt.payer.pubkey();
+  let payer_balance = balance(&mut pt, &payer).await;
+
+  let accounts = TestAccount::builder()
+    .fund_directly()
+    .role("admin")
+    .role_with_lamports("alice", 7_000_000)
+    .build(&mut pt)
+    .await;
+
+  assert_eq!(balance(&mut pt, &accounts.pubkey("alice")).await, 7_000_000);
+  assert_eq!(balance(&mut pt, &payer).await, payer_balance);
+}
+
+#[tokio::test]
+async fn builder_mints_token_balances() {
+  let pt = Arc::new(Mutex::new(ProgramTest::start_new(common::program_test_with_spl()).await));
+  let mut spl = Spl::new(pt.clone());
+  let mint_authority = Keypair::new();
+  let mint_a = spl.create_new_mint(&mint_authority.pubkey(), None, 6).await.pubkey();
+  let mint_b = spl.create_new_mint(&mint_authority.pubkey(), None, 9).await.pubkey();
+
+  let accounts = TestAccount::builder()
+    .roles(&["alice", "bob"])
+    .token_balance("alice", &mint_a, &mint_authority, 100)
+    .token_balance("alice", &mint_b, &mint_authority, 200)
+    .token_balance("bob", &mint_a, &mint_authority, 300)
+    .build(&mut *pt.lock().await)
+    .await;
+
+  let ata = |name: &str, mint: &Pubkey| Spl::get_associated_token_address(&accounts.pubkey(name), mint);
+  assert_eq!(spl.get_token_account(ata("alice", &mint_a)).await.amount, 100);
+  assert_eq!(spl.get_token_account(ata("alice", &mint_b)).await.amount, 200);
+  assert_eq!(spl.get_token_account(ata("bob", &mint_a)).await.amount, 300);
+  assert!(!spl.ata_exists(&accounts.pubkey("bob"), &mint_b).await);
+}
+
+#[test]
+#[should_panic(expected = "TEST-ACCOUNT-ERROR: duplicate role alice")]
+fn duplicate_role_panics() {
+  TestAccount::builder().role("alice").role_with_lamports("alice", 1);
+}
+
+#[test]
+#[should_panic(expected = "TEST-ACCOUNT-ERROR: unknown role bob")]
+fn token_balance_of_unknown_role_panics() {
+  TestAccount::builder().role("alice").token_balance("bob", &Pubkey::new_unique(), &Keypair::new(), 1);
+}
@@ -0,0 +1,127 @@
+use std::{
+  convert::TryInto,
+  time::{Duration, UNIX_EPOCH},
+};
+use solana_program_test::{processor, tokio};
+use solana_sdk::{
+  account_info::AccountInfo,
+  clock::{Clock, UnixTimestamp},
+  entrypoint::ProgramResult,
+  instruction::Instruction,
+  program_error::ProgramError,
+  pubkey::Pubkey,
+  sysvar::Sysvar,
+};
+use solana_test_utils::program_test::ProgramTest;
+
+const DEADLINE_PROGRAM_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+
+/// Fails unless the Clock seen by the program is at or past the deadline given as instruction data
+fn process_deadline(_program_id: &Pubkey, _accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+  let deadline = UnixTimestamp::from_le_bytes(data.try_into().unwrap());
+
+  match Clock::get()?.unix_timestamp >= deadline {
+    true => Ok(()),
+    false => Err(ProgramError::Custom(1)),
+  }
+}
+
+async fn start() -> ProgramTest {
+  let mut program_test = solana_program_test::ProgramTest::default();
+  program_test.add_program("deadline", DEADLINE_PROGRAM_ID, processor!(process_deadline));
+
+  ProgramTest::start_new(program_test).await
+}
+
+fn deadline_ix(deadline: UnixTimestamp) -> Instruction {
+  Instruction::new_with_bytes(DEADLINE_PROGRAM_ID, &deadline.to_le_bytes(), vec![])
+}
+
+#[tokio::test]
+async fn set_unix_timestamp_is_exact() {
+  let mut pt = start().await;
+  let clock = pt.get_clock().await;
+  let deadline = clock.unix_timestamp + 3600;
+
+  pt.set_unix_timestamp(deadline - 1).await;
+  assert_eq!(pt.process_transaction(&[deadline_ix(deadline)], None).await, Err(ProgramError::Custom(1)));
+
+  pt.set_unix_timestamp(deadline).await;
+  pt.process_transaction(&[deadline_ix(deadline)], None).await.unwrap();
+
+  let updated = pt.get_clock().await;
+  assert_eq!(updated.unix_timestamp, deadline);
+  assert_eq!(updated.slot, clock.slot);
+}
+
+#[tokio::test]
+async fn set_clock_overrides_every_field() {
+  let mut pt = start().await;
+  let clock = Clock {
+    slot: 1_000,
+    epoch_start_timestamp: 1_600_000_000,
+    epoch: 7,
+    leader_schedule_epoch: 8,
+    unix_timestamp: 1_700_000_000,
+  };
+
+  pt.set_clock(&clock);
+  assert_eq!(pt.get_clock().await, clock);
+}
+
+#[tokio::test]
+async fn advance_time_by_exact_duration() {
+  let mut pt = start().await;
+  let clock = pt.get_clock().await;
+  let thirty_days = Duration::from_secs(30 * 24 * 3600);
+
+  pt.advance_time(thirty_days).await;
+  let updated = pt.get_clock().await;
+  assert_eq!(updated.unix_timestamp, clock.unix_timestamp + thirty_days.as_secs() as i64);
+  // 400ms per slot
+  assert_eq!(updated.slot, clock.slot + 6_480_000);
+}
+
+#[tokio::test]
+async fn advance_to_exact_time() {
+  let mut pt = start().await;
+  let clock = pt.get_clock().await;
+  let deadline = clock.unix_timestamp + 90;
+
+  pt.advance_to(UNIX_EPOCH + Duration::from_secs(deadline as u64)).await;
+  assert_eq!(pt.get_clock().await.unix_timestamp, deadline);
+  pt.process_transaction(&[deadline_ix(deadline)], None).await.unwrap();
+}
+
+#[tokio::test]
+#[should_panic(expected = "ADVANCE-TIME-ERROR")]
+async fn advance_to_the_past_panics() {
+  let mut pt = start().await;
+  let clock = pt.get_clock().await;
+
+  pt.advance_to_timestamp(clock.unix_timestamp - 1).await;
+}
+
+#[tokio::test]
+async fn advance_epochs() {
+  let mut pt = start().await;
+
+  pt.advance_to_epoch(3).await;
+  let clock = pt.get_clock().await;
+  assert_eq!(clock.epoch, 3);
+  assert_eq!(clock.slot, pt.first_slot_in_epoch(3).await);
+
+  pt.advance_epochs(2).await;
+  let clock = pt.get_clock().await;
+  assert_eq!(clock.epoch, 5);
+  assert_eq!(clock.slot, pt.first_slot_in_epoch(5).await);
+}
+
+#[tokio::test]
+#[should_panic(expected = "ADVANCE-EPOCH-ERROR")]
+async fn advance_to_current_epoch_panics() {
+  let mut pt = start().await;
+  let epoch = pt.get_clock().await.epoch;
+
+  pt.advance_to_epoch(epoch).await;
+}
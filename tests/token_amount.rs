@@ -0,0 +1,35 @@
+use solana_test_utils::utils::TokenAmount;
+
+#[test]
+fn formats_ui_amounts() {
+  assert_eq!(TokenAmount::new(1_500_000, 6).to_string(), "1.5");
+  assert_eq!(TokenAmount::new(1_000_001, 6).to_string(), "1.000001");
+  assert_eq!(TokenAmount::new(42, 0).to_string(), "42");
+  assert_eq!(TokenAmount::new(0, 9).to_string(), "0");
+  assert_eq!(TokenAmount::new(u64::MAX, 19).to_string(), "1.8446744073709551615");
+  assert_eq!(TokenAmount::new(5, 40).to_string(), format!("0.{}5", "0".repeat(39)));
+  assert_eq!(TokenAmount::new(u64::MAX, 255).to_string(), format!("0.{}18446744073709551615", "0".repeat(235)));
+}
+
+#[test]
+fn parses_ui_amounts() {
+  assert_eq!(TokenAmount::parse("1.5", 6).unwrap().amount, 1_500_000);
+  assert_eq!(TokenAmount::parse(".5", 6).unwrap().amount, 500_000);
+  assert_eq!(TokenAmount::parse("1.50000000", 2).unwrap().amount, 150);
+  assert_eq!(TokenAmount::parse("0", 255).unwrap().amount, 0);
+  assert!(TokenAmount::parse("1.234", 2).is_none());
+  assert!(TokenAmount::parse(".", 2).is_none());
+  assert!(TokenAmount::parse("-1", 2).is_none());
+  assert!(TokenAmount::parse("18446744073709551615", 1).is_none());
+}
+
+#[test]
+fn handles_more_than_19_decimals() {
+  let tiny = format!("0.{}5", "0".repeat(39));
+
+  assert_eq!(TokenAmount::parse(&tiny, 40).unwrap().amount, 5);
+  assert_eq!(TokenAmount::parse(&tiny, 40).unwrap().to_string(), tiny);
+  assert_eq!(TokenAmount::from_tokens(0, 40).unwrap().amount, 0);
+  assert!(TokenAmount::from_tokens(1, 20).is_none());
+  assert_eq!(TokenAmount::from_tokens(1, 19).unwrap().amount, 10_000_000_000_000_000_000);
+}